@@ -0,0 +1,535 @@
+//! Feeds arbitrary instruction data and account sets into `Processor::process`.
+//!
+//! The program runs in-process against syscall stubs that serve the clock and
+//! rent sysvars and accept every CPI, so any panic (as opposed to a returned
+//! `ProgramError`) is reported by libFuzzer as a crash.
+//!
+//! Run with `cargo fuzz run process_instruction` from the repository root.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use borsh::BorshSerialize;
+use libfuzzer_sys::fuzz_target;
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{
+        deserialize, ProgramResult, BPF_ALIGN_OF_U128, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER,
+        SUCCESS,
+    },
+    instruction::Instruction,
+    program_option::COption,
+    program_pack::Pack,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program, sysvar,
+};
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+use std::sync::{
+    atomic::{AtomicI64, Ordering},
+    Once,
+};
+use your_staking::{
+    processor::{
+        add_reward_epoch::get_reward_schedule_address,
+        create_user::{get_user_storage_address, get_user_storage_address_and_bump_seed},
+        enable_receipt_token::get_receipt_mint_address,
+        initialize_pool::{
+            get_pool_storage_address, get_rewards_vault_address, get_staking_vault_address,
+        },
+        redeem_receipt::{
+            get_receipt_escrow_authority_and_bump_seed, get_receipt_stake_escrow_address,
+        },
+        Processor,
+    },
+    state::{
+        AccTypesWithVersion, PoolStats, RewardEpoch, RewardSchedule, User, YourPool,
+        MAX_REWARD_EPOCHS, USER_STORAGE_TOTAL_BYTES, USER_V1_STORAGE_TOTAL_BYTES,
+        YOUR_POOL_STORAGE_TOTAL_BYTES, YOUR_POOL_V1_STORAGE_TOTAL_BYTES,
+    },
+    token::spl_token_2022,
+};
+
+const MAX_ACCOUNTS: usize = 14;
+const PROGRAM_ID: Pubkey = Pubkey::new_from_array([0xAA; 32]);
+const STAKING_MINT: Pubkey = Pubkey::new_from_array([0xBB; 32]);
+const REWARDS_MINT: Pubkey = Pubkey::new_from_array([0xDD; 32]);
+const USER_WALLET: Pubkey = Pubkey::new_from_array([0xCC; 32]);
+
+/// Pool storage at index 0 of the fixed mint pair, as created by `InitializePool`.
+fn pool_storage() -> Pubkey {
+    get_pool_storage_address(&STAKING_MINT, &REWARDS_MINT, 0, &PROGRAM_ID)
+}
+
+/// Keys the processors compare against, so that fuzzed account sets can get
+/// past address checks and reach the deeper validation paths.
+#[derive(Arbitrary, Clone, Copy, Debug)]
+enum FuzzKey {
+    Program,
+    TokenProgram,
+    Token2022Program,
+    SystemProgram,
+    RentSysvar,
+    StakingMint,
+    RewardsMint,
+    PoolStorage,
+    PoolSigner,
+    StakingVault,
+    RewardsVault,
+    UserWallet,
+    UserStorage,
+    RewardSchedule,
+    ReceiptMint,
+    ReceiptStakeEscrow,
+    ReceiptEscrowAuthority,
+    Other(u8),
+}
+
+impl FuzzKey {
+    fn pubkey(self) -> Pubkey {
+        match self {
+            FuzzKey::Program => PROGRAM_ID,
+            FuzzKey::TokenProgram => spl_token::id(),
+            FuzzKey::Token2022Program => spl_token_2022::id(),
+            FuzzKey::SystemProgram => system_program::id(),
+            FuzzKey::RentSysvar => sysvar::rent::id(),
+            FuzzKey::StakingMint => STAKING_MINT,
+            FuzzKey::RewardsMint => REWARDS_MINT,
+            FuzzKey::PoolStorage => pool_storage(),
+            FuzzKey::PoolSigner => {
+                Pubkey::find_program_address(&[&pool_storage().to_bytes()], &PROGRAM_ID).0
+            }
+            FuzzKey::StakingVault => get_staking_vault_address(&pool_storage(), &PROGRAM_ID),
+            FuzzKey::RewardsVault => get_rewards_vault_address(&pool_storage(), &PROGRAM_ID),
+            FuzzKey::UserWallet => USER_WALLET,
+            FuzzKey::UserStorage => {
+                get_user_storage_address(&USER_WALLET, &pool_storage(), &PROGRAM_ID)
+            }
+            FuzzKey::RewardSchedule => get_reward_schedule_address(&pool_storage(), &PROGRAM_ID),
+            FuzzKey::ReceiptMint => get_receipt_mint_address(&pool_storage(), &PROGRAM_ID),
+            FuzzKey::ReceiptStakeEscrow => {
+                get_receipt_stake_escrow_address(&pool_storage(), &PROGRAM_ID)
+            }
+            FuzzKey::ReceiptEscrowAuthority => {
+                get_receipt_escrow_authority_and_bump_seed(&pool_storage(), &PROGRAM_ID).0
+            }
+            FuzzKey::Other(seed) => Pubkey::new_from_array([seed; 32]),
+        }
+    }
+}
+
+/// Stored bumps, so that records can carry the real one of their PDA.
+#[derive(Arbitrary, Clone, Copy, Debug)]
+enum FuzzBump {
+    PoolSigner,
+    UserStorage,
+    Other(u8),
+}
+
+impl FuzzBump {
+    fn bump(self) -> u8 {
+        match self {
+            FuzzBump::PoolSigner => {
+                Pubkey::find_program_address(&[&pool_storage().to_bytes()], &PROGRAM_ID).1
+            }
+            FuzzBump::UserStorage => {
+                get_user_storage_address_and_bump_seed(&USER_WALLET, &pool_storage(), &PROGRAM_ID).1
+            }
+            FuzzBump::Other(bump) => bump,
+        }
+    }
+}
+
+#[derive(Arbitrary, Clone, Copy, Debug)]
+enum FuzzVersion {
+    Uninitialized,
+    V1,
+    V2,
+}
+
+#[derive(Arbitrary, Debug)]
+enum FuzzData {
+    Raw(Vec<u8>),
+    Pool {
+        version: FuzzVersion,
+        owner_wallet: FuzzKey,
+        your_staking_vault: FuzzKey,
+        your_reward_rate: u64,
+        your_epoch_duration: u64,
+        user_stake_count: u32,
+        pda_nonce: FuzzBump,
+        reward_duration_end: u64,
+        your_staking_mint: FuzzKey,
+        your_rewards_mint: FuzzKey,
+        your_rewards_vault: FuzzKey,
+        migrated_from: FuzzKey,
+        pool_index: u64,
+        wind_down_date: i64,
+        total_your_staked: u64,
+        reward_per_token_stored: u128,
+        last_update_time: u64,
+        your_rewards_owed: u64,
+        next_epoch_start: u64,
+        your_rewards_scheduled: u64,
+        emission_mode: u8,
+        initial_reward_rate: u64,
+        halving_interval: u64,
+        emission_start: u64,
+        apr_bps: u64,
+        vesting_period: u64,
+        vesting_penalty_bps: u64,
+        your_rewards_vesting: u64,
+        receipt_mint: FuzzKey,
+        accepts_stake_migrations: u8,
+        penalty_stream_remaining: u64,
+        penalty_stream_start: u64,
+        penalty_stream_end: u64,
+    },
+    User {
+        version: FuzzVersion,
+        user_wallet: FuzzKey,
+        your_pool: FuzzKey,
+        balance_your_staked: u64,
+        unstake_pending: u64,
+        unstake_pending_date: i64,
+        nonce: FuzzBump,
+        claim_timeout_date: i64,
+        reward_per_token_complete: u128,
+        reward_per_token_pending: u64,
+        vesting_locked: u64,
+        vesting_start: i64,
+        vesting_end: i64,
+        vesting_unlocked: u64,
+    },
+    Token {
+        mint: FuzzKey,
+        owner: FuzzKey,
+        amount: u64,
+    },
+    Mint {
+        supply: u64,
+        decimals: u8,
+    },
+    RewardSchedule {
+        your_pool: FuzzKey,
+        next_epoch: u8,
+        epochs: Vec<(u64, u64, u64)>,
+    },
+}
+
+impl FuzzData {
+    fn into_bytes(self) -> Vec<u8> {
+        match self {
+            FuzzData::Raw(bytes) => bytes,
+            FuzzData::Pool {
+                version,
+                owner_wallet,
+                your_staking_vault,
+                your_reward_rate,
+                your_epoch_duration,
+                user_stake_count,
+                pda_nonce,
+                reward_duration_end,
+                your_staking_mint,
+                your_rewards_mint,
+                your_rewards_vault,
+                migrated_from,
+                pool_index,
+                wind_down_date,
+                total_your_staked,
+                reward_per_token_stored,
+                last_update_time,
+                your_rewards_owed,
+                next_epoch_start,
+                your_rewards_scheduled,
+                emission_mode,
+                initial_reward_rate,
+                halving_interval,
+                emission_start,
+                apr_bps,
+                vesting_period,
+                vesting_penalty_bps,
+                your_rewards_vesting,
+                receipt_mint,
+                accepts_stake_migrations,
+                penalty_stream_remaining,
+                penalty_stream_start,
+                penalty_stream_end,
+            } => {
+                let (acc_type, len) = match version {
+                    FuzzVersion::Uninitialized => return vec![0u8; YOUR_POOL_STORAGE_TOTAL_BYTES],
+                    FuzzVersion::V1 => (
+                        AccTypesWithVersion::YourPoolDataV1 as u8,
+                        YOUR_POOL_V1_STORAGE_TOTAL_BYTES,
+                    ),
+                    FuzzVersion::V2 => (
+                        AccTypesWithVersion::YourPoolDataV2 as u8,
+                        YOUR_POOL_STORAGE_TOTAL_BYTES,
+                    ),
+                };
+                let pool = YourPool {
+                    acc_type,
+                    owner_wallet: owner_wallet.pubkey(),
+                    your_staking_vault: your_staking_vault.pubkey(),
+                    your_reward_rate,
+                    your_epoch_duration,
+                    user_stake_count,
+                    pda_nonce: pda_nonce.bump(),
+                    reward_duration_end,
+                    your_staking_mint: your_staking_mint.pubkey(),
+                    your_rewards_mint: your_rewards_mint.pubkey(),
+                    your_rewards_vault: your_rewards_vault.pubkey(),
+                    migrated_from: migrated_from.pubkey(),
+                    pool_index,
+                    wind_down_date,
+                    total_your_staked,
+                    reward_per_token_stored,
+                    last_update_time,
+                    your_rewards_owed,
+                    next_epoch_start,
+                    your_rewards_scheduled,
+                    emission_mode,
+                    initial_reward_rate,
+                    halving_interval,
+                    emission_start,
+                    apr_bps,
+                    vesting_period,
+                    vesting_penalty_bps,
+                    your_rewards_vesting,
+                    receipt_mint: receipt_mint.pubkey(),
+                    accepts_stake_migrations,
+                    stats: PoolStats::default(),
+                    penalty_stream_remaining,
+                    penalty_stream_start,
+                    penalty_stream_end,
+                    reserved: [0u8; 60],
+                };
+                let mut bytes = pool.try_to_vec().unwrap();
+                bytes.truncate(len);
+                bytes
+            }
+            FuzzData::User {
+                version,
+                user_wallet,
+                your_pool,
+                balance_your_staked,
+                unstake_pending,
+                unstake_pending_date,
+                nonce,
+                claim_timeout_date,
+                reward_per_token_complete,
+                reward_per_token_pending,
+                vesting_locked,
+                vesting_start,
+                vesting_end,
+                vesting_unlocked,
+            } => {
+                let (acc_type, len) = match version {
+                    FuzzVersion::Uninitialized => return vec![0u8; USER_STORAGE_TOTAL_BYTES],
+                    FuzzVersion::V1 => (
+                        AccTypesWithVersion::UserDataV1 as u8,
+                        USER_V1_STORAGE_TOTAL_BYTES,
+                    ),
+                    FuzzVersion::V2 => (
+                        AccTypesWithVersion::UserDataV2 as u8,
+                        USER_STORAGE_TOTAL_BYTES,
+                    ),
+                };
+                let user = User {
+                    acc_type,
+                    user_wallet: user_wallet.pubkey(),
+                    your_pool: your_pool.pubkey(),
+                    balance_your_staked,
+                    unstake_pending,
+                    unstake_pending_date,
+                    nonce: nonce.bump(),
+                    claim_timeout_date,
+                    reward_per_token_complete,
+                    reward_per_token_pending,
+                    vesting_locked,
+                    vesting_start,
+                    vesting_end,
+                    vesting_unlocked,
+                    reserved: [0u8; 102],
+                };
+                let mut bytes = user.try_to_vec().unwrap();
+                bytes.truncate(len);
+                bytes
+            }
+            FuzzData::Token {
+                mint,
+                owner,
+                amount,
+            } => {
+                let account = TokenAccount {
+                    mint: mint.pubkey(),
+                    owner: owner.pubkey(),
+                    amount,
+                    delegate: COption::None,
+                    state: AccountState::Initialized,
+                    is_native: COption::None,
+                    delegated_amount: 0,
+                    close_authority: COption::None,
+                };
+                let mut bytes = vec![0u8; TokenAccount::LEN];
+                TokenAccount::pack(account, &mut bytes).unwrap();
+                bytes
+            }
+            FuzzData::Mint { supply, decimals } => {
+                let mint = Mint {
+                    mint_authority: COption::None,
+                    supply,
+                    decimals,
+                    is_initialized: true,
+                    freeze_authority: COption::None,
+                };
+                let mut bytes = vec![0u8; Mint::LEN];
+                Mint::pack(mint, &mut bytes).unwrap();
+                bytes
+            }
+            FuzzData::RewardSchedule {
+                your_pool,
+                next_epoch,
+                epochs,
+            } => {
+                let mut schedule = RewardSchedule {
+                    acc_type: AccTypesWithVersion::RewardScheduleV1 as u8,
+                    your_pool: your_pool.pubkey(),
+                    next_epoch,
+                    epoch_count: 0,
+                    epochs: [RewardEpoch::default(); MAX_REWARD_EPOCHS],
+                };
+                for (slot, (start_time, duration, amount)) in schedule.epochs.iter_mut().zip(epochs)
+                {
+                    *slot = RewardEpoch {
+                        start_time,
+                        duration,
+                        amount,
+                    };
+                    schedule.epoch_count += 1;
+                }
+                schedule.try_to_vec().unwrap()
+            }
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzAccount {
+    key: FuzzKey,
+    owner: FuzzKey,
+    is_signer: bool,
+    is_writable: bool,
+    lamports: u64,
+    data: FuzzData,
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzInput {
+    unix_timestamp: i64,
+    accounts: Vec<FuzzAccount>,
+    instruction_data: Vec<u8>,
+}
+
+struct FuzzSyscallStubs;
+
+static UNIX_TIMESTAMP: AtomicI64 = AtomicI64::new(0);
+
+impl SyscallStubs for FuzzSyscallStubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_invoke_signed(
+        &self,
+        _instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        Ok(())
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            *(var_addr as *mut Clock) = Clock {
+                unix_timestamp: UNIX_TIMESTAMP.load(Ordering::Relaxed),
+                ..Clock::default()
+            };
+        }
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            *(var_addr as *mut Rent) = Rent::default();
+        }
+        SUCCESS
+    }
+}
+
+/// Lays the accounts out the way the runtime hands them to a program, so that
+/// `AccountInfo::realloc` finds the room it expects after the account data.
+fn serialize_input(accounts: Vec<FuzzAccount>, instruction_data: &[u8]) -> Vec<u64> {
+    fn push_u64(buffer: &mut Vec<u8>, value: u64) {
+        buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    let mut buffer: Vec<u8> = Vec::new();
+    push_u64(&mut buffer, accounts.len() as u64);
+    let mut keys: Vec<Pubkey> = Vec::with_capacity(accounts.len());
+    for account in accounts {
+        let key = account.key.pubkey();
+        // The runtime passes duplicated keys by index of their first occurrence
+        if let Some(index) = keys.iter().position(|existing| *existing == key) {
+            buffer.push(index as u8);
+            buffer.extend_from_slice(&[0u8; 7]);
+            keys.push(key);
+            continue;
+        }
+        keys.push(key);
+        let data = account.data.into_bytes();
+        buffer.push(NON_DUP_MARKER);
+        buffer.push(account.is_signer as u8);
+        buffer.push(account.is_writable as u8);
+        buffer.push(0u8);
+        buffer.extend_from_slice(&[0u8; 4]);
+        buffer.extend_from_slice(key.as_ref());
+        buffer.extend_from_slice(account.owner.pubkey().as_ref());
+        push_u64(&mut buffer, account.lamports);
+        push_u64(&mut buffer, data.len() as u64);
+        buffer.extend_from_slice(&data);
+        buffer.resize(buffer.len() + MAX_PERMITTED_DATA_INCREASE, 0u8);
+        let padding = (BPF_ALIGN_OF_U128 - buffer.len() % BPF_ALIGN_OF_U128) % BPF_ALIGN_OF_U128;
+        buffer.resize(buffer.len() + padding, 0u8);
+        push_u64(&mut buffer, 0u64);
+    }
+    push_u64(&mut buffer, instruction_data.len() as u64);
+    buffer.extend_from_slice(instruction_data);
+    buffer.extend_from_slice(PROGRAM_ID.as_ref());
+
+    // u64 words keep the buffer aligned like the runtime's
+    let mut aligned = vec![0u64; (buffer.len() + 7) / 8];
+    for (word, chunk) in aligned.iter_mut().zip(buffer.chunks(8)) {
+        let mut bytes = [0u8; 8];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        *word = u64::from_le_bytes(bytes);
+    }
+    aligned
+}
+
+static INIT: Once = Once::new();
+
+fuzz_target!(|input: FuzzInput| {
+    INIT.call_once(|| {
+        set_syscall_stubs(Box::new(FuzzSyscallStubs));
+    });
+    UNIX_TIMESTAMP.store(input.unix_timestamp, Ordering::Relaxed);
+
+    let accounts: Vec<FuzzAccount> = input.accounts.into_iter().take(MAX_ACCOUNTS).collect();
+    let mut buffer = serialize_input(accounts, &input.instruction_data);
+    let (program_id, account_infos, instruction_data) =
+        unsafe { deserialize(buffer.as_mut_ptr() as *mut u8) };
+
+    let _ = Processor::process(program_id, &account_infos, instruction_data);
+});
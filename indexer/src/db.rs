@@ -0,0 +1,211 @@
+use crate::logs::{program_events, TransactionLogs};
+use rusqlite::{params, Connection, Row};
+use solana_program::pubkey::Pubkey;
+use std::{path::Path, str::FromStr};
+use your_staking::event::{StakingEvent, StakingEventKind};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    kind INTEGER NOT NULL,
+    pool TEXT NOT NULL,
+    user TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    balance_your_staked INTEGER NOT NULL,
+    unstake_pending INTEGER NOT NULL,
+    timestamp INTEGER NOT NULL,
+    tvl_delta INTEGER NOT NULL,
+    UNIQUE (signature, event_index)
+);
+CREATE INDEX IF NOT EXISTS events_by_user ON events (pool, user, slot);
+CREATE INDEX IF NOT EXISTS events_by_pool ON events (pool, slot);
+";
+
+/// One decoded event as stored in the database.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventRecord {
+    pub signature: String,
+    pub slot: u64,
+    pub event: StakingEvent,
+}
+
+/// Tokens held in a pool's staking vault right after an event.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TvlPoint {
+    pub slot: u64,
+    pub timestamp: i64,
+    pub tvl: u64,
+}
+
+pub struct Indexer {
+    conn: Connection,
+}
+
+impl Indexer {
+    pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Indexer { conn })
+    }
+
+    /// Stores the events `program_id` logged in a transaction and returns how
+    /// many were new. Failed transactions are skipped and re-indexing the same
+    /// signature is a no-op.
+    pub fn index_transaction(
+        &mut self,
+        program_id: &Pubkey,
+        transaction: &TransactionLogs,
+    ) -> rusqlite::Result<usize> {
+        if transaction.err.is_some() {
+            return Ok(0);
+        }
+
+        let db_transaction = self.conn.transaction()?;
+        let mut inserted = 0;
+        for (event_index, event) in program_events(program_id, &transaction.logs)
+            .iter()
+            .enumerate()
+        {
+            inserted += db_transaction.execute(
+                "INSERT OR IGNORE INTO events (
+                    signature, event_index, slot, kind, pool, user, amount,
+                    balance_your_staked, unstake_pending, timestamp, tvl_delta
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    transaction.signature,
+                    event_index as i64,
+                    transaction.slot as i64,
+                    event.kind as u8,
+                    event.pool.to_string(),
+                    event.user.to_string(),
+                    event.amount as i64,
+                    event.balance_your_staked as i64,
+                    event.unstake_pending as i64,
+                    event.timestamp,
+                    tvl_delta(event),
+                ],
+            )?;
+        }
+        db_transaction.commit()?;
+        Ok(inserted)
+    }
+
+    /// Every event of `user` in `pool`, oldest first.
+    pub fn user_history(&self, pool: &Pubkey, user: &Pubkey) -> rusqlite::Result<Vec<EventRecord>> {
+        let mut statement = self.conn.prepare(
+            "SELECT signature, slot, kind, pool, user, amount, balance_your_staked,
+                    unstake_pending, timestamp
+             FROM events WHERE pool = ?1 AND user = ?2 ORDER BY slot, id",
+        )?;
+        let rows =
+            statement.query_map(params![pool.to_string(), user.to_string()], event_record)?;
+        rows.collect()
+    }
+
+    /// Staking vault balance of `pool` after each event that moved tokens in
+    /// or out of it, oldest first.
+    pub fn pool_tvl_history(&self, pool: &Pubkey) -> rusqlite::Result<Vec<TvlPoint>> {
+        let mut statement = self.conn.prepare(
+            "SELECT slot, timestamp, tvl FROM (
+                SELECT slot, timestamp, tvl_delta,
+                       SUM(tvl_delta) OVER (ORDER BY slot, id ROWS UNBOUNDED PRECEDING) AS tvl
+                FROM events WHERE pool = ?1
+             ) WHERE tvl_delta != 0",
+        )?;
+        let rows = statement.query_map(params![pool.to_string()], |row| {
+            Ok(TvlPoint {
+                slot: row.get::<_, i64>(0)? as u64,
+                timestamp: row.get(1)?,
+                tvl: row.get::<_, i64>(2)? as u64,
+            })
+        })?;
+        rows.collect()
+    }
+}
+
+/// Change of the staking vault balance caused by an event. Unstake only marks
+/// tokens as pending, they leave the vault with FinalUnstake or ForceReturnStake.
+/// RedeemReceipt pays from the receipt stake escrow, which ForceReturnStake
+/// already took out of the vault.
+fn tvl_delta(event: &StakingEvent) -> i64 {
+    match event.kind {
+        StakingEventKind::Stake | StakingEventKind::MigrateStakeIn => event.amount as i64,
+        StakingEventKind::FinalUnstake
+        | StakingEventKind::ForceReturnStake
+        | StakingEventKind::MigrateStakeOut => -(event.amount as i64),
+        // ClosePool requires an empty staking vault, WindDownPool and
+        // RecoverRewards only move rewards
+        StakingEventKind::Unstake
+        | StakingEventKind::ClaimRewards
+        | StakingEventKind::FundPool
+        | StakingEventKind::ClosePool
+        | StakingEventKind::CloseUser
+        | StakingEventKind::WindDownPool
+        | StakingEventKind::RecoverRewards
+        | StakingEventKind::AddRewardEpoch
+        | StakingEventKind::StartHalvingEmission
+        | StakingEventKind::StartFixedApr
+        | StakingEventKind::WithdrawVested
+        | StakingEventKind::TransferPosition
+        | StakingEventKind::RedeemReceipt => 0,
+    }
+}
+
+fn event_kind(tag: u8) -> Option<StakingEventKind> {
+    Some(match tag {
+        0 => StakingEventKind::Stake,
+        1 => StakingEventKind::Unstake,
+        2 => StakingEventKind::FinalUnstake,
+        3 => StakingEventKind::ClaimRewards,
+        4 => StakingEventKind::FundPool,
+        5 => StakingEventKind::ClosePool,
+        6 => StakingEventKind::CloseUser,
+        7 => StakingEventKind::WindDownPool,
+        8 => StakingEventKind::ForceReturnStake,
+        9 => StakingEventKind::RecoverRewards,
+        10 => StakingEventKind::AddRewardEpoch,
+        11 => StakingEventKind::StartHalvingEmission,
+        12 => StakingEventKind::StartFixedApr,
+        13 => StakingEventKind::WithdrawVested,
+        14 => StakingEventKind::RedeemReceipt,
+        15 => StakingEventKind::TransferPosition,
+        16 => StakingEventKind::MigrateStakeOut,
+        17 => StakingEventKind::MigrateStakeIn,
+        _ => return None,
+    })
+}
+
+fn pubkey_column(row: &Row, index: usize) -> rusqlite::Result<Pubkey> {
+    let value: String = row.get(index)?;
+    Pubkey::from_str(&value).map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(err))
+    })
+}
+
+fn event_record(row: &Row) -> rusqlite::Result<EventRecord> {
+    let tag: u8 = row.get(2)?;
+    let kind = event_kind(tag).ok_or(rusqlite::Error::IntegralValueOutOfRange(2, tag as i64))?;
+    Ok(EventRecord {
+        signature: row.get(0)?,
+        slot: row.get::<_, i64>(1)? as u64,
+        event: StakingEvent {
+            kind,
+            pool: pubkey_column(row, 3)?,
+            user: pubkey_column(row, 4)?,
+            amount: row.get::<_, i64>(5)? as u64,
+            balance_your_staked: row.get::<_, i64>(6)? as u64,
+            unstake_pending: row.get::<_, i64>(7)? as u64,
+            timestamp: row.get(8)?,
+        },
+    })
+}
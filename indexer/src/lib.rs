@@ -0,0 +1,8 @@
+//! Rebuilds pool history from the `StakingEvent`s the program logs.
+//!
+//! Transactions are fed in as their log messages (the shape of a
+//! `logsSubscribe` notification), decoded with `logs::program_events` and
+//! stored by `db::Indexer` in a SQLite database.
+
+pub mod db;
+pub mod logs;
@@ -0,0 +1,53 @@
+use serde::Deserialize;
+use solana_program::pubkey::Pubkey;
+use your_staking::event::StakingEvent;
+
+/// Log messages of one transaction, as delivered by `logsSubscribe` with the
+/// slot of the notification context added.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TransactionLogs {
+    pub signature: String,
+    pub slot: u64,
+    #[serde(default)]
+    pub err: Option<serde_json::Value>,
+    pub logs: Vec<String>,
+}
+
+/// Decodes the staking events emitted by `program_id` in a transaction's logs.
+///
+/// The invoke stack is tracked so that `Program data:` lines logged by other
+/// programs in the same transaction are never attributed to the pool.
+pub fn program_events(program_id: &Pubkey, logs: &[String]) -> Vec<StakingEvent> {
+    let program_id = program_id.to_string();
+    let mut invoke_stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for log in logs {
+        if let Some(rest) = log.strip_prefix("Program ") {
+            let mut words = rest.split(' ');
+            // `Program log:` and `Program data:` lines are program output
+            match (words.next(), words.next()) {
+                (Some(program), Some("invoke")) if !program.ends_with(':') => {
+                    invoke_stack.push(program);
+                    continue;
+                }
+                (Some(program), Some("success")) | (Some(program), Some("failed:"))
+                    if !program.ends_with(':') =>
+                {
+                    invoke_stack.pop();
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        if invoke_stack.last() != Some(&program_id.as_str()) {
+            continue;
+        }
+        if let Some(event) = StakingEvent::decode_log(log) {
+            events.push(event);
+        }
+    }
+
+    events
+}
@@ -0,0 +1,83 @@
+//! Command line front end for the indexer.
+//!
+//! ```text
+//! your_staking_indexer <database> index <program-id> [logs.jsonl]
+//! your_staking_indexer <database> history <pool> <user>
+//! your_staking_indexer <database> tvl <pool>
+//! ```
+//!
+//! `index` reads one `TransactionLogs` JSON object per line, from the given
+//! file or from stdin.
+
+use solana_program::pubkey::Pubkey;
+use std::{
+    env,
+    error::Error,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    process,
+    str::FromStr,
+};
+use your_staking_indexer::{db::Indexer, logs::TransactionLogs};
+
+const USAGE: &str = "usage:
+    your_staking_indexer <database> index <program-id> [logs.jsonl]
+    your_staking_indexer <database> history <pool> <user>
+    your_staking_indexer <database> tvl <pool>";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        [database, "index", program_id, rest @ ..] if rest.len() <= 1 => {
+            let program_id = Pubkey::from_str(program_id)?;
+            let input: Box<dyn BufRead> = match rest.first() {
+                Some(path) => Box::new(BufReader::new(File::open(path)?)),
+                None => Box::new(BufReader::new(io::stdin())),
+            };
+            let mut indexer = Indexer::open(database)?;
+            let mut inserted = 0;
+            for line in input.lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let transaction: TransactionLogs = serde_json::from_str(&line)?;
+                inserted += indexer.index_transaction(&program_id, &transaction)?;
+            }
+            println!("indexed {} new events", inserted);
+        }
+        [database, "history", pool, user] => {
+            let indexer = Indexer::open(database)?;
+            let history =
+                indexer.user_history(&Pubkey::from_str(pool)?, &Pubkey::from_str(user)?)?;
+            for record in history {
+                println!(
+                    "{} {} {:?} amount={} staked={} pending={} time={}",
+                    record.slot,
+                    record.signature,
+                    record.event.kind,
+                    record.event.amount,
+                    record.event.balance_your_staked,
+                    record.event.unstake_pending,
+                    record.event.timestamp,
+                );
+            }
+        }
+        [database, "tvl", pool] => {
+            let indexer = Indexer::open(database)?;
+            for point in indexer.pool_tvl_history(&Pubkey::from_str(pool)?)? {
+                println!("{} {} {}", point.slot, point.timestamp, point.tvl);
+            }
+        }
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use borsh::BorshSerialize;
+use solana_program::pubkey::Pubkey;
+use your_staking::event::{StakingEvent, StakingEventKind, EVENT_DISCRIMINATOR};
+use your_staking_indexer::{
+    db::{Indexer, TvlPoint},
+    logs::{program_events, TransactionLogs},
+};
+
+const PROGRAM_ID: Pubkey = Pubkey::new_from_array([0xAA; 32]);
+const POOL: Pubkey = Pubkey::new_from_array([0xBB; 32]);
+const USER: Pubkey = Pubkey::new_from_array([0xCC; 32]);
+
+fn event(kind: StakingEventKind, amount: u64, balance_your_staked: u64) -> StakingEvent {
+    StakingEvent {
+        kind,
+        pool: POOL,
+        user: USER,
+        amount,
+        balance_your_staked,
+        unstake_pending: 0,
+        timestamp: 1_700_000_000,
+    }
+}
+
+fn data_log(event: &StakingEvent) -> String {
+    format!(
+        "Program data: {} {}",
+        base64::encode(EVENT_DISCRIMINATOR),
+        base64::encode(event.try_to_vec().unwrap())
+    )
+}
+
+/// Logs of a transaction in which `PROGRAM_ID` emits `event`.
+fn transaction(signature: &str, slot: u64, event: &StakingEvent) -> TransactionLogs {
+    TransactionLogs {
+        signature: signature.to_string(),
+        slot,
+        err: None,
+        logs: vec![
+            format!("Program {} invoke [1]", PROGRAM_ID),
+            "Program log: Instruction::Stake".to_string(),
+            data_log(event),
+            format!(
+                "Program {} consumed 5000 of 200000 compute units",
+                PROGRAM_ID
+            ),
+            format!("Program {} success", PROGRAM_ID),
+        ],
+    }
+}
+
+#[test]
+fn only_events_of_the_program_are_decoded() {
+    let stake = event(StakingEventKind::Stake, 100, 100);
+    let other_program = Pubkey::new_from_array([0xDD; 32]);
+    let logs = vec![
+        format!("Program {} invoke [1]", other_program),
+        data_log(&stake),
+        format!("Program {} invoke [2]", PROGRAM_ID),
+        data_log(&stake),
+        format!("Program {} success", PROGRAM_ID),
+        data_log(&stake),
+        format!("Program {} success", other_program),
+    ];
+
+    assert_eq!(program_events(&PROGRAM_ID, &logs), vec![stake]);
+}
+
+#[test]
+fn builds_user_history_and_tvl() {
+    let mut indexer = Indexer::open_in_memory().unwrap();
+    let stake = event(StakingEventKind::Stake, 1_000, 1_000);
+    let unstake = event(StakingEventKind::Unstake, 400, 1_000);
+    let final_unstake = event(StakingEventKind::FinalUnstake, 400, 600);
+
+    assert_eq!(
+        indexer
+            .index_transaction(&PROGRAM_ID, &transaction("a", 10, &stake))
+            .unwrap(),
+        1
+    );
+    indexer
+        .index_transaction(&PROGRAM_ID, &transaction("b", 11, &unstake))
+        .unwrap();
+    indexer
+        .index_transaction(&PROGRAM_ID, &transaction("c", 12, &final_unstake))
+        .unwrap();
+    // re-delivered notifications are ignored
+    assert_eq!(
+        indexer
+            .index_transaction(&PROGRAM_ID, &transaction("a", 10, &stake))
+            .unwrap(),
+        0
+    );
+
+    let history = indexer.user_history(&POOL, &USER).unwrap();
+    let kinds: Vec<StakingEventKind> = history.iter().map(|record| record.event.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            StakingEventKind::Stake,
+            StakingEventKind::Unstake,
+            StakingEventKind::FinalUnstake
+        ]
+    );
+    assert_eq!(history[2].event, final_unstake);
+
+    let tvl: Vec<u64> = indexer
+        .pool_tvl_history(&POOL)
+        .unwrap()
+        .iter()
+        .map(|point: &TvlPoint| point.tvl)
+        .collect();
+    assert_eq!(tvl, vec![1_000, 600]);
+}
+
+#[test]
+fn failed_transactions_are_skipped() {
+    let mut indexer = Indexer::open_in_memory().unwrap();
+    let mut failed = transaction("a", 10, &event(StakingEventKind::Stake, 1_000, 1_000));
+    failed.err = Some(serde_json::json!({ "InstructionError": [0, { "Custom": 1 }] }));
+
+    assert_eq!(indexer.index_transaction(&PROGRAM_ID, &failed).unwrap(), 0);
+    assert!(indexer.user_history(&POOL, &USER).unwrap().is_empty());
+}
+
+#[test]
+fn migrated_stake_moves_tvl_between_pools() {
+    let mut indexer = Indexer::open_in_memory().unwrap();
+    let new_pool = Pubkey::new_from_array([0xEE; 32]);
+    let migrate_out = event(StakingEventKind::MigrateStakeOut, 1_000, 0);
+    let migrate_in = StakingEvent {
+        pool: new_pool,
+        ..event(StakingEventKind::MigrateStakeIn, 1_000, 1_000)
+    };
+    indexer
+        .index_transaction(
+            &PROGRAM_ID,
+            &transaction("a", 10, &event(StakingEventKind::Stake, 1_000, 1_000)),
+        )
+        .unwrap();
+
+    let mut migrate = transaction("b", 11, &migrate_out);
+    migrate.logs.insert(3, data_log(&migrate_in));
+    assert_eq!(indexer.index_transaction(&PROGRAM_ID, &migrate).unwrap(), 2);
+
+    let tvl = |pool: &Pubkey| -> Vec<u64> {
+        indexer
+            .pool_tvl_history(pool)
+            .unwrap()
+            .iter()
+            .map(|point| point.tvl)
+            .collect()
+    };
+    assert_eq!(tvl(&POOL), vec![1_000, 0]);
+    assert_eq!(tvl(&new_pool), vec![1_000]);
+    assert_eq!(
+        indexer.user_history(&new_pool, &USER).unwrap()[0].event,
+        migrate_in
+    );
+}
@@ -1,4 +1,5 @@
 export * from './your-staking-instructions';
 export * from './your-pool-info';
 export * from './user-info';
-export * from './get-user-pending-rewards';
\ No newline at end of file
+export * from './get-user-pending-rewards';
+export * from './pool-stats';
\ No newline at end of file
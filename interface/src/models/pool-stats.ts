@@ -0,0 +1,93 @@
+import { Buffer } from 'buffer';
+import { PublicKey } from '@solana/web3.js';
+import { deserializeUnchecked } from 'borsh';
+import BN from 'bn.js';
+import { ConnectionService } from '../config';
+import { extendBorsh } from '../data/borsch';
+import { Constants } from '../constants';
+
+/** Cumulative statistics kept in the pool storage account. */
+export class PoolStatsData {
+    rewardsFunded: BN;
+    rewardsClaimed: BN;
+    totalStaked: BN;
+    totalUnstaked: BN;
+    activeStakers: number;
+    lastActivityDate: BN;
+
+    constructor(args: {
+        rewardsFunded: BN;
+        rewardsClaimed: BN;
+        totalStaked: BN;
+        totalUnstaked: BN;
+        activeStakers: number;
+        lastActivityDate: BN;
+    }) {
+        this.rewardsFunded = args.rewardsFunded;
+        this.rewardsClaimed = args.rewardsClaimed;
+        this.totalStaked = args.totalStaked;
+        this.totalUnstaked = args.totalUnstaked;
+        this.activeStakers = args.activeStakers;
+        this.lastActivityDate = args.lastActivityDate;
+    }
+
+    getRewardsFunded(): number {
+        return this.rewardsFunded.div(new BN(Constants.toRewardTokenRaw)).toNumber();
+    }
+
+    getRewardsClaimed(): number {
+        return this.rewardsClaimed.div(new BN(Constants.toRewardTokenRaw)).toNumber();
+    }
+
+    getTotalStaked(): number {
+        return this.totalStaked.div(new BN(Constants.toYourRaw)).toNumber();
+    }
+
+    getTotalUnstaked(): number {
+        return this.totalUnstaked.div(new BN(Constants.toYourRaw)).toNumber();
+    }
+
+    getActiveStakers(): number {
+        return this.activeStakers;
+    }
+
+    getLastActivityDate(): number {
+        return this.lastActivityDate.toNumber();
+    }
+
+    static async fromPoolAccount(pool: PublicKey): Promise<PoolStatsData | null> {
+        const connection = ConnectionService.getConnection();
+        const accountData = await connection.getAccountInfo(pool);
+        if (!accountData) return null;
+        return PoolStatsData.fromPoolBuffer(accountData?.data);
+    }
+
+    static fromPoolBuffer(buffer: Buffer): PoolStatsData {
+        extendBorsh();
+        return deserializeUnchecked(
+            POOL_STATS_ON_CHAIN_SCHEMA,
+            PoolStatsData,
+            buffer.slice(POOL_STATS_OFFSET, POOL_STATS_OFFSET + POOL_STATS_TOTAL_BYTES)
+        );
+    }
+}
+
+export const POOL_STATS_OFFSET = 384;
+export const POOL_STATS_TOTAL_BYTES = 44;
+
+export const POOL_STATS_ON_CHAIN_SCHEMA = new Map<any, any>([
+    [
+        PoolStatsData,
+        {
+            kind: 'struct',
+            fields: [
+                ['rewardsFunded', 'u64'],
+                ['rewardsClaimed', 'u64'],
+                ['totalStaked', 'u64'],
+                ['totalUnstaked', 'u64'],
+                ['activeStakers', 'u32'],
+                ['lastActivityDate', 'u64'],
+            ],
+        },
+    ],
+]);
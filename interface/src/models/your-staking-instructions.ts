@@ -6,5 +6,21 @@ export enum YourStakingInstructions {
   ClaimRewards = 4,
   ClosePool = 5,
   CloseUser = 6,
-  FinalUnstake = 7
+  FinalUnstake = 7,
+  MigratePool = 8,
+  MigrateUser = 9,
+  WindDownPool = 10,
+  ForceReturnStake = 11,
+  RecoverRewards = 12,
+  CheckPoolInvariants = 13,
+  AddRewardEpoch = 14,
+  StartHalvingEmission = 15,
+  StartFixedApr = 16,
+  ConfigureVesting = 17,
+  WithdrawVested = 18,
+  EnableReceiptToken = 19,
+  RedeemReceipt = 20,
+  TransferPosition = 21,
+  AcceptStakeMigrations = 22,
+  MigrateStake = 23
 }
@@ -0,0 +1,41 @@
+import {
+    PublicKey,
+    Transaction,
+    TransactionInstruction,
+} from '@solana/web3.js';
+import { Pubkeys } from '../constants';
+import { ConnectionService } from '../config';
+import { YourStakingInstructions } from '../models';
+export async function acceptStakeMigrationsTransaction(
+    poolOwnerWallet: PublicKey,
+    accept: boolean
+): Promise<Transaction> {
+    const connection = ConnectionService.getConnection();
+
+    const acceptStakeMigrationsIx = new TransactionInstruction({
+        programId: Pubkeys.yourStakingProgramId,
+        keys: [
+            {
+                pubkey: poolOwnerWallet,
+                isSigner: true,
+                isWritable: false,
+            },
+            {
+                pubkey: Pubkeys.yourPoolStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+        ],
+        data: Buffer.from([
+            YourStakingInstructions.AcceptStakeMigrations,
+            accept ? 1 : 0,
+        ]),
+    });
+    const acceptStakeMigrationsTx = new Transaction().add(acceptStakeMigrationsIx);
+    acceptStakeMigrationsTx.recentBlockhash = (
+        await connection.getRecentBlockhash()
+    ).blockhash;
+    acceptStakeMigrationsTx.feePayer = poolOwnerWallet;
+
+    return acceptStakeMigrationsTx;
+}
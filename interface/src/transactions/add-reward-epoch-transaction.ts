@@ -0,0 +1,86 @@
+import {
+    PublicKey,
+    SystemProgram,
+    Transaction,
+    TransactionInstruction,
+} from '@solana/web3.js';
+import { findAssociatedTokenAddress, getRewardScheduleAccount } from '../utils';
+import { Constants, Pubkeys } from '../constants';
+import { ConnectionService } from '../config';
+import { YourStakingInstructions } from '../models';
+import BN from 'bn.js';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+export async function addRewardEpochTransaction(
+    poolOwnerWallet: PublicKey,
+    startTime: number,
+    duration: number,
+    rewardAmount: number
+): Promise<Transaction> {
+    const connection = ConnectionService.getConnection();
+
+    const rewardScheduleStorage = await getRewardScheduleAccount(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
+    const rewardsATAPubkey = await findAssociatedTokenAddress(
+        poolOwnerWallet,
+        Pubkeys.rewardsMintPubkey
+    );
+
+    const rewardAmountRaw = new BN(rewardAmount).mul(new BN(Constants.toYourRaw));
+
+    const addRewardEpochIx = new TransactionInstruction({
+        programId: Pubkeys.yourStakingProgramId,
+        keys: [
+            {
+                pubkey: poolOwnerWallet,
+                isSigner: true,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.yourPoolStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: rewardScheduleStorage,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.yourRewardsVaultPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: rewardsATAPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.rewardsMintPubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            {
+                pubkey: SystemProgram.programId,
+                isSigner: false,
+                isWritable: false,
+            },
+        ],
+        data: Buffer.from([
+            YourStakingInstructions.AddRewardEpoch,
+            ...new BN(startTime).toArray('le', 8),
+            ...new BN(duration).toArray('le', 8),
+            ...rewardAmountRaw.toArray('le', 8),
+        ]),
+    });
+    const addRewardEpochTx = new Transaction().add(addRewardEpochIx);
+    addRewardEpochTx.recentBlockhash = (
+        await connection.getRecentBlockhash()
+    ).blockhash;
+    addRewardEpochTx.feePayer = poolOwnerWallet;
+
+    return addRewardEpochTx;
+}
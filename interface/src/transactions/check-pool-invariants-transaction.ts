@@ -0,0 +1,40 @@
+import {
+    PublicKey,
+    Transaction,
+    TransactionInstruction,
+} from '@solana/web3.js';
+import { Pubkeys } from '../constants';
+import { ConnectionService } from '../config';
+import { YourStakingInstructions } from '../models';
+export async function checkPoolInvariantsTransaction(
+    feePayer: PublicKey,
+    userStoragePubkeys: PublicKey[]
+): Promise<Transaction> {
+    const connection = ConnectionService.getConnection();
+
+    const checkPoolInvariantsIx = new TransactionInstruction({
+        programId: Pubkeys.yourStakingProgramId,
+        keys: [
+            {
+                pubkey: Pubkeys.yourPoolStoragePubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            ...userStoragePubkeys.map((pubkey) => ({
+                pubkey,
+                isSigner: false,
+                isWritable: false,
+            })),
+        ],
+        data: Buffer.from([
+            YourStakingInstructions.CheckPoolInvariants
+        ]),
+    });
+    const checkPoolInvariantsTx = new Transaction().add(checkPoolInvariantsIx);
+    checkPoolInvariantsTx.recentBlockhash = (
+        await connection.getRecentBlockhash()
+    ).blockhash;
+    checkPoolInvariantsTx.feePayer = feePayer;
+
+    return checkPoolInvariantsTx;
+}
@@ -3,7 +3,12 @@ import {
     Transaction,
     TransactionInstruction,
 } from '@solana/web3.js';
-import { findAssociatedTokenAddress, getPoolSignerPDA, getUserStorageAccount } from '../utils';
+import {
+    findAssociatedTokenAddress,
+    getPoolSignerPDA,
+    getRewardScheduleAccount,
+    getUserStorageAccount,
+} from '../utils';
 import { Pubkeys } from '../constants';
 import { ConnectionService } from '../config';
 import { YourStakingInstructions } from '../models';
@@ -29,7 +34,11 @@ export async function claimRewardsTransaction(
       const doesRewardsAtaExist =
         rewardsAtaInfo?.owner !== undefined;
     
-      const claimRewardsIxs: TransactionInstruction[] = [];
+      const rewardScheduleStorage = await getRewardScheduleAccount(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
+    const claimRewardsIxs: TransactionInstruction[] = [];
       if (!doesRewardsAtaExist) {
         const createFantAssociatedAccountIx =
           Token.createAssociatedTokenAccountInstruction(
@@ -87,6 +96,16 @@ export async function claimRewardsTransaction(
                 isWritable: false,
             },
             { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            {
+                pubkey: Pubkeys.rewardsMintPubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            {
+                pubkey: rewardScheduleStorage,
+                isSigner: false,
+                isWritable: true,
+            },
         ],
         data: Buffer.from([
             YourStakingInstructions.ClaimRewards
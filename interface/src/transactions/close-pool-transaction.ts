@@ -3,7 +3,12 @@ import {
     Transaction,
     TransactionInstruction,
 } from '@solana/web3.js';
-import { findAssociatedTokenAddress, getPoolSignerPDA, getUserStorageAccount } from '../utils';
+import {
+    findAssociatedTokenAddress,
+    getPoolSignerPDA,
+    getRewardScheduleAccount,
+    getUserStorageAccount,
+} from '../utils';
 import { Pubkeys } from '../constants';
 import { ConnectionService } from '../config';
 import { YourStakingInstructions } from '../models';
@@ -68,13 +73,17 @@ export async function closePoolTransaction(
 
     const poolSignerPda = await getPoolSignerPDA();
 
+    const rewardScheduleStorage = await getRewardScheduleAccount(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
     const closePoolIx = new TransactionInstruction({
         programId: Pubkeys.yourStakingProgramId,
         keys: [
             {
                 pubkey: poolOwnerWallet,
                 isSigner: true,
-                isWritable: false,
+                isWritable: true,
             },
 
             {
@@ -110,6 +119,22 @@ export async function closePoolTransaction(
                 isWritable: false,
             },
             { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            {
+                pubkey: Pubkeys.stakingMintPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.rewardsMintPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            {
+                pubkey: rewardScheduleStorage,
+                isSigner: false,
+                isWritable: true,
+            },
         ],
         data: Buffer.from([
             YourStakingInstructions.ClosePool
@@ -3,7 +3,7 @@ import {
     Transaction,
     TransactionInstruction,
 } from '@solana/web3.js';
-import { getUserStorageAccount } from '../utils';
+import { getRewardScheduleAccount, getUserStorageAccount } from '../utils';
 import { Pubkeys } from '../constants';
 import { ConnectionService } from '../config';
 import { YourStakingInstructions } from '../models';
@@ -16,6 +16,10 @@ export async function closeUserTransaction(
         userWallet
     );
 
+    const rewardScheduleStorage = await getRewardScheduleAccount(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
     const closeUserIx = new TransactionInstruction({
         programId: Pubkeys.yourStakingProgramId,
         keys: [
@@ -36,6 +40,11 @@ export async function closeUserTransaction(
                 isSigner: false,
                 isWritable: true,
             },
+            {
+                pubkey: rewardScheduleStorage,
+                isSigner: false,
+                isWritable: true,
+            },
         ],
         data: Buffer.from([
             YourStakingInstructions.CloseUser
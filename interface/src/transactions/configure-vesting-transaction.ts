@@ -0,0 +1,44 @@
+import {
+    PublicKey,
+    Transaction,
+    TransactionInstruction,
+} from '@solana/web3.js';
+import { Pubkeys } from '../constants';
+import { ConnectionService } from '../config';
+import { YourStakingInstructions } from '../models';
+import BN from 'bn.js';
+export async function configureVestingTransaction(
+    poolOwnerWallet: PublicKey,
+    vestingPeriod: number,
+    penaltyBps: number
+): Promise<Transaction> {
+    const connection = ConnectionService.getConnection();
+
+    const configureVestingIx = new TransactionInstruction({
+        programId: Pubkeys.yourStakingProgramId,
+        keys: [
+            {
+                pubkey: poolOwnerWallet,
+                isSigner: true,
+                isWritable: false,
+            },
+            {
+                pubkey: Pubkeys.yourPoolStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+        ],
+        data: Buffer.from([
+            YourStakingInstructions.ConfigureVesting,
+            ...new BN(vestingPeriod).toArray('le', 8),
+            ...new BN(penaltyBps).toArray('le', 8),
+        ]),
+    });
+    const configureVestingTx = new Transaction().add(configureVestingIx);
+    configureVestingTx.recentBlockhash = (
+        await connection.getRecentBlockhash()
+    ).blockhash;
+    configureVestingTx.feePayer = poolOwnerWallet;
+
+    return configureVestingTx;
+}
@@ -0,0 +1,94 @@
+import {
+    PublicKey,
+    SystemProgram,
+    SYSVAR_RENT_PUBKEY,
+    Transaction,
+    TransactionInstruction,
+} from '@solana/web3.js';
+import {
+    getPoolSignerPDA,
+    getReceiptEscrowAuthority,
+    getReceiptMint,
+    getReceiptStakeEscrowAccount,
+} from '../utils';
+import { Pubkeys } from '../constants';
+import { ConnectionService } from '../config';
+import { YourStakingInstructions } from '../models';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+export async function enableReceiptTokenTransaction(
+    poolOwnerWallet: PublicKey
+): Promise<Transaction> {
+    const connection = ConnectionService.getConnection();
+
+    const receiptMint = await getReceiptMint(Pubkeys.yourPoolStoragePubkey);
+
+    const poolSignerPda = await getPoolSignerPDA();
+
+    const receiptStakeEscrow = await getReceiptStakeEscrowAccount(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
+    const receiptEscrowAuthority = await getReceiptEscrowAuthority(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
+    const enableReceiptTokenIx = new TransactionInstruction({
+        programId: Pubkeys.yourStakingProgramId,
+        keys: [
+            {
+                pubkey: poolOwnerWallet,
+                isSigner: true,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.yourPoolStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: receiptMint,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.stakingMintPubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            {
+                pubkey: poolSignerPda,
+                isSigner: false,
+                isWritable: false,
+            },
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            {
+                pubkey: SystemProgram.programId,
+                isSigner: false,
+                isWritable: false,
+            },
+            {
+                pubkey: SYSVAR_RENT_PUBKEY,
+                isSigner: false,
+                isWritable: false,
+            },
+            {
+                pubkey: receiptStakeEscrow,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: receiptEscrowAuthority,
+                isSigner: false,
+                isWritable: false,
+            },
+        ],
+        data: Buffer.from([YourStakingInstructions.EnableReceiptToken]),
+    });
+    const enableReceiptTokenTx = new Transaction().add(enableReceiptTokenIx);
+    enableReceiptTokenTx.recentBlockhash = (
+        await connection.getRecentBlockhash()
+    ).blockhash;
+    enableReceiptTokenTx.feePayer = poolOwnerWallet;
+
+    return enableReceiptTokenTx;
+}
@@ -3,7 +3,13 @@ import {
     Transaction,
     TransactionInstruction,
 } from '@solana/web3.js';
-import { findAssociatedTokenAddress, getPoolSignerPDA, getUserStorageAccount } from '../utils';
+import {
+    findAssociatedTokenAddress,
+    getPoolSignerPDA,
+    getReceiptMint,
+    getRewardScheduleAccount,
+    getUserStorageAccount,
+} from '../utils';
 import { Constants, Pubkeys } from '../constants';
 import { ConnectionService } from '../config';
 import { YourStakingInstructions } from '../models';
@@ -25,6 +31,17 @@ export async function finalUnstakeYourTransaction(
 
     const poolSignerPda = await getPoolSignerPDA();
 
+    const rewardScheduleStorage = await getRewardScheduleAccount(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
+    const receiptMint = await getReceiptMint(Pubkeys.yourPoolStoragePubkey);
+
+    const receiptATAPubkey = await findAssociatedTokenAddress(
+        userWallet,
+        receiptMint
+    );
+
     const unstakeYourIx = new TransactionInstruction({
         programId: Pubkeys.yourStakingProgramId,
         keys: [
@@ -62,6 +79,26 @@ export async function finalUnstakeYourTransaction(
                 isWritable: false,
             },
             { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            {
+                pubkey: Pubkeys.stakingMintPubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            {
+                pubkey: rewardScheduleStorage,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: receiptMint,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: receiptATAPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
         ],
         data: Buffer.from([
             YourStakingInstructions.FinalUnstake
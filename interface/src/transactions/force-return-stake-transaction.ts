@@ -0,0 +1,119 @@
+import {
+    PublicKey,
+    Transaction,
+    TransactionInstruction,
+} from '@solana/web3.js';
+import {
+    findAssociatedTokenAddress,
+    getPoolSignerPDA,
+    getReceiptStakeEscrowAccount,
+    getUserStorageAccount,
+} from '../utils';
+import { Pubkeys } from '../constants';
+import { ConnectionService } from '../config';
+import { YourStakingInstructions } from '../models';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+export async function forceReturnStakeTransaction(
+    poolOwnerWallet: PublicKey,
+    userWallet: PublicKey
+): Promise<Transaction> {
+    const connection = ConnectionService.getConnection();
+
+    const userStoragePubkey = await getUserStorageAccount(
+        userWallet
+    );
+
+    const stakingAssociatedAccPubkey = await findAssociatedTokenAddress(
+        userWallet,
+        Pubkeys.stakingMintPubkey
+    );
+
+    const rewardsAssociatedAccPubkey = await findAssociatedTokenAddress(
+        userWallet,
+        Pubkeys.rewardsMintPubkey
+    );
+
+    const poolSignerPda = await getPoolSignerPDA();
+
+    const receiptStakeEscrow = await getReceiptStakeEscrowAccount(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
+    const forceReturnStakeIx = new TransactionInstruction({
+        programId: Pubkeys.yourStakingProgramId,
+        keys: [
+            {
+                pubkey: poolOwnerWallet,
+                isSigner: true,
+                isWritable: false,
+            },
+            {
+                pubkey: Pubkeys.yourPoolStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: userStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: userWallet,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.yourStakingVaultPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: stakingAssociatedAccPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.stakingMintPubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            {
+                pubkey: poolSignerPda,
+                isSigner: false,
+                isWritable: false,
+            },
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            {
+                pubkey: Pubkeys.yourRewardsVaultPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: rewardsAssociatedAccPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.rewardsMintPubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            {
+                pubkey: receiptStakeEscrow,
+                isSigner: false,
+                isWritable: true,
+            },
+        ],
+        data: Buffer.from([
+            YourStakingInstructions.ForceReturnStake
+        ]),
+    });
+    const forceReturnStakeTx = new Transaction().add(forceReturnStakeIx);
+    forceReturnStakeTx.recentBlockhash = (
+        await connection.getRecentBlockhash()
+    ).blockhash;
+    forceReturnStakeTx.feePayer = poolOwnerWallet;
+
+    return forceReturnStakeTx;
+}
@@ -5,4 +5,18 @@ export * from './claim-rewards-transaction';
 export * from './unstake-your-transaction';
 export * from './close-pool-transaction';
 export * from './close-user-transaction';
+export * from './wind-down-pool-transaction';
+export * from './force-return-stake-transaction';
+export * from './recover-rewards-transaction';
+export * from './check-pool-invariants-transaction';
 
+export * from './add-reward-epoch-transaction';
+export * from './start-halving-emission-transaction';
+export * from './start-fixed-apr-transaction';
+export * from './configure-vesting-transaction';
+export * from './withdraw-vested-transaction';
+export * from './enable-receipt-token-transaction';
+export * from './redeem-receipt-transaction';
+export * from './transfer-position-transaction';
+export * from './accept-stake-migrations-transaction';
+export * from './migrate-stake-transaction';
\ No newline at end of file
@@ -0,0 +1,124 @@
+import {
+    PublicKey,
+    Transaction,
+    TransactionInstruction,
+} from '@solana/web3.js';
+import {
+    getPoolSignerPDA,
+    getRewardScheduleAccount,
+    getRewardsVaultAccount,
+    getStakingVaultAccount,
+    getUserStorageAccount,
+} from '../utils';
+import { Pubkeys } from '../constants';
+import { ConnectionService } from '../config';
+import { YourStakingInstructions } from '../models';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+export async function migrateStakeTransaction(
+    userWallet: PublicKey,
+    destinationPoolStorage: PublicKey
+): Promise<Transaction> {
+    const connection = ConnectionService.getConnection();
+
+    const userStoragePubkey = await getUserStorageAccount(userWallet);
+
+    const poolSignerPda = await getPoolSignerPDA();
+
+    const destinationStoragePubkey = (
+        await PublicKey.findProgramAddress(
+            [userWallet.toBuffer(), destinationPoolStorage.toBuffer()],
+            Pubkeys.yourStakingProgramId
+        )
+    )[0];
+
+    const destinationStakingVault = await getStakingVaultAccount(
+        destinationPoolStorage
+    );
+
+    const rewardScheduleStorage = await getRewardScheduleAccount(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
+    const destinationRewardScheduleStorage = await getRewardScheduleAccount(
+        destinationPoolStorage
+    );
+
+    const destinationRewardsVault = await getRewardsVaultAccount(
+        destinationPoolStorage
+    );
+
+    const migrateStakeIx = new TransactionInstruction({
+        programId: Pubkeys.yourStakingProgramId,
+        keys: [
+            {
+                pubkey: userWallet,
+                isSigner: true,
+                isWritable: false,
+            },
+            {
+                pubkey: userStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.yourPoolStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.yourStakingVaultPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: poolSignerPda,
+                isSigner: false,
+                isWritable: false,
+            },
+            {
+                pubkey: destinationStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: destinationPoolStorage,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: destinationStakingVault,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.stakingMintPubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            {
+                pubkey: rewardScheduleStorage,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: destinationRewardScheduleStorage,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: destinationRewardsVault,
+                isSigner: false,
+                isWritable: false,
+            },
+        ],
+        data: Buffer.from([YourStakingInstructions.MigrateStake]),
+    });
+    const migrateStakeTx = new Transaction().add(migrateStakeIx);
+    migrateStakeTx.recentBlockhash = (
+        await connection.getRecentBlockhash()
+    ).blockhash;
+    migrateStakeTx.feePayer = userWallet;
+
+    return migrateStakeTx;
+}
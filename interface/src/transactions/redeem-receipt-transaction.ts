@@ -0,0 +1,102 @@
+import {
+    PublicKey,
+    Transaction,
+    TransactionInstruction,
+} from '@solana/web3.js';
+import {
+    findAssociatedTokenAddress,
+    getReceiptEscrowAuthority,
+    getReceiptMint,
+    getReceiptStakeEscrowAccount,
+} from '../utils';
+import { Constants, Pubkeys } from '../constants';
+import { ConnectionService } from '../config';
+import { YourStakingInstructions } from '../models';
+import BN from 'bn.js';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+export async function redeemReceiptTransaction(
+    holderWallet: PublicKey,
+    amountToRedeem: number
+): Promise<Transaction> {
+    const connection = ConnectionService.getConnection();
+
+    const receiptMint = await getReceiptMint(Pubkeys.yourPoolStoragePubkey);
+
+    const holderReceiptATAPubkey = await findAssociatedTokenAddress(
+        holderWallet,
+        receiptMint
+    );
+
+    const holderStakingATAPubkey = await findAssociatedTokenAddress(
+        holderWallet,
+        Pubkeys.stakingMintPubkey
+    );
+
+    const receiptStakeEscrow = await getReceiptStakeEscrowAccount(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
+    const receiptEscrowAuthority = await getReceiptEscrowAuthority(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
+    const amountToRedeemRaw = new BN(amountToRedeem).mul(new BN(Constants.toYourRaw));
+
+    const redeemReceiptIx = new TransactionInstruction({
+        programId: Pubkeys.yourStakingProgramId,
+        keys: [
+            {
+                pubkey: holderWallet,
+                isSigner: true,
+                isWritable: false,
+            },
+            {
+                pubkey: holderReceiptATAPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: receiptMint,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.yourPoolStoragePubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            {
+                pubkey: receiptStakeEscrow,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: holderStakingATAPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.stakingMintPubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            {
+                pubkey: receiptEscrowAuthority,
+                isSigner: false,
+                isWritable: false,
+            },
+        ],
+        data: Buffer.from([
+            YourStakingInstructions.RedeemReceipt,
+            ...amountToRedeemRaw.toArray('le', 8),
+        ]),
+    });
+    const redeemReceiptTx = new Transaction().add(redeemReceiptIx);
+    redeemReceiptTx.recentBlockhash = (
+        await connection.getRecentBlockhash()
+    ).blockhash;
+    redeemReceiptTx.feePayer = holderWallet;
+
+    return redeemReceiptTx;
+}
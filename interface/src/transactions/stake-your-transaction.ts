@@ -3,7 +3,13 @@ import {
     Transaction,
     TransactionInstruction,
 } from '@solana/web3.js';
-import { findAssociatedTokenAddress, getUserStorageAccount } from '../utils';
+import {
+    findAssociatedTokenAddress,
+    getPoolSignerPDA,
+    getReceiptMint,
+    getRewardScheduleAccount,
+    getUserStorageAccount,
+} from '../utils';
 import { Constants, Pubkeys } from '../constants';
 import { ConnectionService } from '../config';
 import { YourStakingInstructions } from '../models';
@@ -24,6 +30,19 @@ export async function stakeYourTransaction(
         Pubkeys.stakingMintPubkey
     );
 
+    const rewardScheduleStorage = await getRewardScheduleAccount(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
+    const receiptMint = await getReceiptMint(Pubkeys.yourPoolStoragePubkey);
+
+    const receiptATAPubkey = await findAssociatedTokenAddress(
+        userWallet,
+        receiptMint
+    );
+
+    const poolSignerPda = await getPoolSignerPDA();
+
     const amountToDepositRaw = new BN(amountToDeposit).mul(new BN(Constants.toYourRaw));
 
     const stakeYourIx = new TransactionInstruction({
@@ -58,6 +77,36 @@ export async function stakeYourTransaction(
                 isWritable: true,
             },
             { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            {
+                pubkey: Pubkeys.stakingMintPubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            {
+                pubkey: rewardScheduleStorage,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.yourRewardsVaultPubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            {
+                pubkey: receiptMint,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: receiptATAPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: poolSignerPda,
+                isSigner: false,
+                isWritable: false,
+            },
         ],
         data: Buffer.from([
             YourStakingInstructions.StakeYour, ...amountToDepositRaw.toArray('le', 8)
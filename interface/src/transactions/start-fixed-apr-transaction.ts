@@ -0,0 +1,71 @@
+import {
+    PublicKey,
+    Transaction,
+    TransactionInstruction,
+} from '@solana/web3.js';
+import { findAssociatedTokenAddress } from '../utils';
+import { Constants, Pubkeys } from '../constants';
+import { ConnectionService } from '../config';
+import { YourStakingInstructions } from '../models';
+import BN from 'bn.js';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+export async function startFixedAprTransaction(
+    poolOwnerWallet: PublicKey,
+    aprBps: number,
+    duration: number,
+    rewardAmount: number
+): Promise<Transaction> {
+    const connection = ConnectionService.getConnection();
+
+    const rewardsATAPubkey = await findAssociatedTokenAddress(
+        poolOwnerWallet,
+        Pubkeys.rewardsMintPubkey
+    );
+
+    const rewardAmountRaw = new BN(rewardAmount).mul(new BN(Constants.toYourRaw));
+
+    const startFixedAprIx = new TransactionInstruction({
+        programId: Pubkeys.yourStakingProgramId,
+        keys: [
+            {
+                pubkey: poolOwnerWallet,
+                isSigner: true,
+                isWritable: false,
+            },
+            {
+                pubkey: Pubkeys.yourPoolStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.yourRewardsVaultPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: rewardsATAPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.rewardsMintPubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        ],
+        data: Buffer.from([
+            YourStakingInstructions.StartFixedApr,
+            ...new BN(aprBps).toArray('le', 8),
+            ...new BN(duration).toArray('le', 8),
+            ...rewardAmountRaw.toArray('le', 8),
+        ]),
+    });
+    const startFixedAprTx = new Transaction().add(startFixedAprIx);
+    startFixedAprTx.recentBlockhash = (
+        await connection.getRecentBlockhash()
+    ).blockhash;
+    startFixedAprTx.feePayer = poolOwnerWallet;
+
+    return startFixedAprTx;
+}
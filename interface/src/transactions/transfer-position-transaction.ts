@@ -0,0 +1,69 @@
+import {
+    PublicKey,
+    Transaction,
+    TransactionInstruction,
+} from '@solana/web3.js';
+import { getRewardScheduleAccount, getUserStorageAccount } from '../utils';
+import { Pubkeys } from '../constants';
+import { ConnectionService } from '../config';
+import { YourStakingInstructions } from '../models';
+export async function transferPositionTransaction(
+    userWallet: PublicKey,
+    destinationWallet: PublicKey
+): Promise<Transaction> {
+    const connection = ConnectionService.getConnection();
+
+    const userStoragePubkey = await getUserStorageAccount(userWallet);
+
+    const destinationStoragePubkey = await getUserStorageAccount(
+        destinationWallet
+    );
+
+    const rewardScheduleStorage = await getRewardScheduleAccount(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
+    const transferPositionIx = new TransactionInstruction({
+        programId: Pubkeys.yourStakingProgramId,
+        keys: [
+            {
+                pubkey: userWallet,
+                isSigner: true,
+                isWritable: false,
+            },
+            {
+                pubkey: userStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: destinationWallet,
+                isSigner: false,
+                isWritable: false,
+            },
+            {
+                pubkey: destinationStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.yourPoolStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: rewardScheduleStorage,
+                isSigner: false,
+                isWritable: true,
+            },
+        ],
+        data: Buffer.from([YourStakingInstructions.TransferPosition]),
+    });
+    const transferPositionTx = new Transaction().add(transferPositionIx);
+    transferPositionTx.recentBlockhash = (
+        await connection.getRecentBlockhash()
+    ).blockhash;
+    transferPositionTx.feePayer = userWallet;
+
+    return transferPositionTx;
+}
@@ -0,0 +1,82 @@
+import {
+    PublicKey,
+    Transaction,
+    TransactionInstruction,
+} from '@solana/web3.js';
+import {
+    findAssociatedTokenAddress,
+    getPoolSignerPDA,
+    getRewardScheduleAccount,
+} from '../utils';
+import { Pubkeys } from '../constants';
+import { ConnectionService } from '../config';
+import { YourStakingInstructions } from '../models';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+export async function windDownPoolTransaction(
+    poolOwnerWallet: PublicKey
+): Promise<Transaction> {
+    const connection = ConnectionService.getConnection();
+
+    const rewardsRefundeeATAPubkey = await findAssociatedTokenAddress(
+        poolOwnerWallet,
+        Pubkeys.rewardsMintPubkey
+    );
+
+    const poolSignerPda = await getPoolSignerPDA();
+
+    const rewardScheduleStorage = await getRewardScheduleAccount(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
+    const windDownPoolIx = new TransactionInstruction({
+        programId: Pubkeys.yourStakingProgramId,
+        keys: [
+            {
+                pubkey: poolOwnerWallet,
+                isSigner: true,
+                isWritable: false,
+            },
+            {
+                pubkey: Pubkeys.yourPoolStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.yourRewardsVaultPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: rewardsRefundeeATAPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.rewardsMintPubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            {
+                pubkey: poolSignerPda,
+                isSigner: false,
+                isWritable: false,
+            },
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            {
+                pubkey: rewardScheduleStorage,
+                isSigner: false,
+                isWritable: true,
+            },
+        ],
+        data: Buffer.from([
+            YourStakingInstructions.WindDownPool
+        ]),
+    });
+    const windDownPoolTx = new Transaction().add(windDownPoolIx);
+    windDownPoolTx.recentBlockhash = (
+        await connection.getRecentBlockhash()
+    ).blockhash;
+    windDownPoolTx.feePayer = poolOwnerWallet;
+
+    return windDownPoolTx;
+}
@@ -0,0 +1,92 @@
+import {
+    PublicKey,
+    Transaction,
+    TransactionInstruction,
+} from '@solana/web3.js';
+import {
+    findAssociatedTokenAddress,
+    getPoolSignerPDA,
+    getRewardScheduleAccount,
+    getUserStorageAccount,
+} from '../utils';
+import { Pubkeys } from '../constants';
+import { ConnectionService } from '../config';
+import { YourStakingInstructions } from '../models';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+export async function withdrawVestedTransaction(
+    userWallet: PublicKey,
+    instant: boolean
+): Promise<Transaction> {
+    const connection = ConnectionService.getConnection();
+
+    const userStoragePubkey = await getUserStorageAccount(userWallet);
+
+    const rewardsATAPubkey = await findAssociatedTokenAddress(
+        userWallet,
+        Pubkeys.rewardsMintPubkey
+    );
+
+    const poolSignerPda = await getPoolSignerPDA();
+
+    const rewardScheduleStorage = await getRewardScheduleAccount(
+        Pubkeys.yourPoolStoragePubkey
+    );
+
+    const withdrawVestedIx = new TransactionInstruction({
+        programId: Pubkeys.yourStakingProgramId,
+        keys: [
+            {
+                pubkey: userWallet,
+                isSigner: true,
+                isWritable: false,
+            },
+            {
+                pubkey: userStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.yourPoolStoragePubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.yourRewardsVaultPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: rewardsATAPubkey,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: Pubkeys.rewardsMintPubkey,
+                isSigner: false,
+                isWritable: false,
+            },
+            {
+                pubkey: poolSignerPda,
+                isSigner: false,
+                isWritable: false,
+            },
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            {
+                pubkey: rewardScheduleStorage,
+                isSigner: false,
+                isWritable: true,
+            },
+        ],
+        data: Buffer.from([
+            YourStakingInstructions.WithdrawVested,
+            instant ? 1 : 0,
+        ]),
+    });
+    const withdrawVestedTx = new Transaction().add(withdrawVestedIx);
+    withdrawVestedTx.recentBlockhash = (
+        await connection.getRecentBlockhash()
+    ).blockhash;
+    withdrawVestedTx.feePayer = userWallet;
+
+    return withdrawVestedTx;
+}
@@ -0,0 +1,30 @@
+import { PublicKey } from '@solana/web3.js';
+import BN from 'bn.js';
+import { Pubkeys } from '../constants';
+
+export async function getPoolStorageAccountWithNonce(
+    stakingMint: PublicKey,
+    rewardsMint: PublicKey,
+    poolIndex: number
+): Promise<[PublicKey, Number]> {
+    return (
+        await PublicKey.findProgramAddress(
+            [
+                stakingMint.toBuffer(),
+                rewardsMint.toBuffer(),
+                new BN(poolIndex).toArrayLike(Buffer, 'le', 8),
+            ],
+            Pubkeys.yourStakingProgramId
+        )
+    );
+}
+
+export async function getPoolStorageAccount(
+    stakingMint: PublicKey,
+    rewardsMint: PublicKey,
+    poolIndex: number
+): Promise<PublicKey> {
+    return (
+        await getPoolStorageAccountWithNonce(stakingMint, rewardsMint, poolIndex)
+    )[0];
+}
@@ -0,0 +1,24 @@
+import { PublicKey } from '@solana/web3.js';
+import { Pubkeys } from '../constants';
+
+export async function getReceiptStakeEscrowAccount(
+    poolStorage: PublicKey
+): Promise<PublicKey> {
+    return (
+        await PublicKey.findProgramAddress(
+            [Buffer.from('receipt_stake_escrow'), poolStorage.toBuffer()],
+            Pubkeys.yourStakingProgramId
+        )
+    )[0];
+}
+
+export async function getReceiptEscrowAuthority(
+    poolStorage: PublicKey
+): Promise<PublicKey> {
+    return (
+        await PublicKey.findProgramAddress(
+            [Buffer.from('receipt_escrow'), poolStorage.toBuffer()],
+            Pubkeys.yourStakingProgramId
+        )
+    )[0];
+}
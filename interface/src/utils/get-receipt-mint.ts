@@ -0,0 +1,13 @@
+import { PublicKey } from '@solana/web3.js';
+import { Pubkeys } from '../constants';
+
+export async function getReceiptMint(
+    poolStorage: PublicKey
+): Promise<PublicKey> {
+    return (
+        await PublicKey.findProgramAddress(
+            [Buffer.from('receipt_mint'), poolStorage.toBuffer()],
+            Pubkeys.yourStakingProgramId
+        )
+    )[0];
+}
@@ -0,0 +1,13 @@
+import { PublicKey } from '@solana/web3.js';
+import { Pubkeys } from '../constants';
+
+export async function getRewardScheduleAccount(
+    poolStorage: PublicKey
+): Promise<PublicKey> {
+    return (
+        await PublicKey.findProgramAddress(
+            [Buffer.from('reward_schedule'), poolStorage.toBuffer()],
+            Pubkeys.yourStakingProgramId
+        )
+    )[0];
+}
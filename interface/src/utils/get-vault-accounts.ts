@@ -0,0 +1,24 @@
+import { PublicKey } from '@solana/web3.js';
+import { Pubkeys } from '../constants';
+
+export async function getStakingVaultAccount(
+    poolStorage: PublicKey
+): Promise<PublicKey> {
+    return (
+        await PublicKey.findProgramAddress(
+            [Buffer.from('staking_vault'), poolStorage.toBuffer()],
+            Pubkeys.yourStakingProgramId
+        )
+    )[0];
+}
+
+export async function getRewardsVaultAccount(
+    poolStorage: PublicKey
+): Promise<PublicKey> {
+    return (
+        await PublicKey.findProgramAddress(
+            [Buffer.from('rewards_vault'), poolStorage.toBuffer()],
+            Pubkeys.yourStakingProgramId
+        )
+    )[0];
+}
@@ -1,4 +1,9 @@
 export * from './find-associated-token-address';
 export * from './conversions';
 export * from './get-user-storage-account';
-export * from './get-pool-signer-pda';
\ No newline at end of file
+export * from './get-pool-signer-pda';
+export * from './get-pool-storage-account';
+export * from './get-vault-accounts';
+export * from './get-reward-schedule-account';
+export * from './get-receipt-mint';
+export * from './get-receipt-escrow-accounts';
\ No newline at end of file
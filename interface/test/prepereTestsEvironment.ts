@@ -3,7 +3,12 @@ import {Keypair, sendAndConfirmTransaction, Transaction} from "@solana/web3.js";
 import {Constants, Pubkeys} from "../src/constants";
 import {ConnectionService} from "../src/config";
 import {ASSOCIATED_TOKEN_PROGRAM_ID, Token, TOKEN_PROGRAM_ID, u64} from '@solana/spl-token';
-import {findAssociatedTokenAddress} from "../src/utils";
+import {
+    findAssociatedTokenAddress,
+    getPoolStorageAccount,
+    getRewardsVaultAccount,
+    getStakingVaultAccount
+} from "../src/utils";
 import BN from "bn.js";
 import {
     createInitializePoolTransaction, createUserTransaction
@@ -11,9 +16,7 @@ import {
 
 const adminAccount: Keypair = getAdminAccount();
 const walletAccount: Keypair = Keypair.generate();
-let yourPoolStorageAccount: Keypair;
-let yourStakingVault: Keypair;
-let yourRewardsVault: Keypair;
+const poolIndex: number = 0;
 const rewardDurationInDays: number = 1 / 86400;
 const yourDecimals = 9;
 const rewardTokenDecimals = 9;
@@ -49,10 +52,6 @@ async function setupEnvironment() {
     );
     Pubkeys.rewardsMintPubkey = rewardTokenMint.publicKey;
 
-    yourPoolStorageAccount = Keypair.generate();
-    yourStakingVault = Keypair.generate();
-    yourRewardsVault = Keypair.generate();
-
     const funderRewardTokenData = await findAssociatedTokenAddress(
         adminAccount.publicKey,
         Pubkeys.rewardsMintPubkey
@@ -92,9 +91,13 @@ async function setupEnvironment() {
     );
 
     await requestAirdrop(adminAccount.publicKey);
-    Pubkeys.yourPoolStoragePubkey = yourPoolStorageAccount.publicKey;
-    Pubkeys.yourStakingVaultPubkey = yourStakingVault.publicKey;
-    Pubkeys.yourRewardsVaultPubkey = yourRewardsVault.publicKey;
+    Pubkeys.yourPoolStoragePubkey = await getPoolStorageAccount(
+        Pubkeys.stakingMintPubkey,
+        Pubkeys.rewardsMintPubkey,
+        poolIndex
+    );
+    Pubkeys.yourStakingVaultPubkey = await getStakingVaultAccount(Pubkeys.yourPoolStoragePubkey);
+    Pubkeys.yourRewardsVaultPubkey = await getRewardsVaultAccount(Pubkeys.yourPoolStoragePubkey);
 
     await requestAirdrop(walletAccount.publicKey);
 
@@ -135,8 +138,6 @@ export {
     adminAccount,
     walletAccount,
     setupEnvironment,
-    yourPoolStorageAccount,
-    yourStakingVault,
-    yourRewardsVault,
+    poolIndex,
     rewardDurationInDays
 }
\ No newline at end of file
@@ -70,6 +70,69 @@ pub enum CustomError {
     // Pool Address Already Initialized
     #[error("User final unstake timeout didn't expired")]
     UserFinalUnstakeTimeout,
+    /// Invalid Rewards Vault
+    #[error("Invalid Rewards Vault")]
+    InvalidRewardsVault,
+    /// Pool Not Migrated
+    #[error("Pool Not Migrated")]
+    PoolNotMigrated,
+    /// Unsupported Mint Extension
+    #[error("Unsupported Mint Extension")]
+    UnsupportedMintExtension,
+    /// Pool Winding Down
+    #[error("Pool Winding Down")]
+    PoolWindingDown,
+    /// Pool Not Winding Down
+    #[error("Pool Not Winding Down")]
+    PoolNotWindingDown,
+    /// Wind down grace period didn't expire
+    #[error("Wind down grace period didn't expire")]
+    WindDownGracePeriod,
+    /// Invalid User Token Account
+    #[error("Invalid User Token Account")]
+    InvalidUserTokenAccount,
+    /// User Position Not Empty
+    #[error("User Position Not Empty")]
+    UserPositionNotEmpty,
+    /// Invalid Reward Schedule
+    #[error("Invalid Reward Schedule")]
+    InvalidRewardSchedule,
+    /// Reward Schedule Full
+    #[error("Reward Schedule Full")]
+    RewardScheduleFull,
+    /// Reward epoch overlaps the current or a scheduled one
+    #[error("Reward epoch overlaps the current or a scheduled one")]
+    RewardEpochOverlap,
+    /// Invalid Halving Interval
+    #[error("Invalid Halving Interval")]
+    InvalidHalvingInterval,
+    /// Projected APR rewards exceed the rewards vault
+    #[error("Projected APR rewards exceed the rewards vault")]
+    InsufficientRewardsForApr,
+    /// Invalid Vesting Penalty
+    #[error("Invalid Vesting Penalty")]
+    InvalidVestingPenalty,
+    /// Invalid Receipt Mint
+    #[error("Invalid Receipt Mint")]
+    InvalidReceiptMint,
+    /// Pool Has Users
+    #[error("Pool Has Users")]
+    PoolHasUsers,
+    /// Position owner still holds the receipts
+    #[error("Position owner still holds the receipts")]
+    ReceiptsStillHeld,
+    /// Not supported by receipt token pools
+    #[error("Not supported by receipt token pools")]
+    ReceiptPoolUnsupported,
+    /// Destination pool does not accept stake migrations
+    #[error("Destination pool does not accept stake migrations")]
+    StakeMigrationNotAccepted,
+    /// User Not Migrated
+    #[error("User Not Migrated")]
+    UserNotMigrated,
+    /// Pool Address Was Closed
+    #[error("Pool Address Was Closed")]
+    PoolAddressClosed,
 }
 
 impl From<CustomError> for ProgramError {
@@ -0,0 +1,75 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{log::sol_log_data, program_error::ProgramError, pubkey::Pubkey};
+
+/// First `sol_log_data` field of every event, so indexers can tell staking
+/// events apart from other data logged in the same transaction.
+pub const EVENT_DISCRIMINATOR: [u8; 8] = *b"yourstk1";
+
+/// Prefix the runtime puts in front of `sol_log_data` output in transaction logs.
+pub const PROGRAM_DATA_LOG_PREFIX: &str = "Program data: ";
+
+/// Appended to only, indexers rely on the Borsh tag of each variant.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum StakingEventKind {
+    Stake,
+    Unstake,
+    FinalUnstake,
+    ClaimRewards,
+    FundPool,
+    ClosePool,
+    CloseUser,
+    WindDownPool,
+    ForceReturnStake,
+    RecoverRewards,
+    AddRewardEpoch,
+    StartHalvingEmission,
+    StartFixedApr,
+    WithdrawVested,
+    RedeemReceipt,
+    TransferPosition,
+    MigrateStakeOut,
+    MigrateStakeIn,
+}
+
+/// Emitted once per state-changing instruction, `TransferPosition` and
+/// `MigrateStake` emit one for each of the two users. Pool-level events carry the acting wallet in
+/// `user` and leave the user balances at zero.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct StakingEvent {
+    pub kind: StakingEventKind,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub balance_your_staked: u64,
+    pub unstake_pending: u64,
+    pub timestamp: i64,
+}
+
+impl StakingEvent {
+    pub fn emit(&self) -> Result<(), ProgramError> {
+        sol_log_data(&[&EVENT_DISCRIMINATOR, &self.try_to_vec()?]);
+        Ok(())
+    }
+
+    /// Decodes the fields of a `sol_log_data` call, `None` if they are not a staking event.
+    pub fn decode(fields: &[&[u8]]) -> Option<Self> {
+        match fields {
+            [discriminator, data] if *discriminator == EVENT_DISCRIMINATOR => {
+                StakingEvent::try_from_slice(data).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Decodes a `Program data: ...` line from transaction logs.
+    pub fn decode_log(log: &str) -> Option<Self> {
+        let fields = log
+            .strip_prefix(PROGRAM_DATA_LOG_PREFIX)?
+            .split(' ')
+            .map(base64::decode)
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+        let fields: Vec<&[u8]> = fields.iter().map(Vec::as_slice).collect();
+        Self::decode(&fields)
+    }
+}
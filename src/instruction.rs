@@ -6,6 +6,7 @@ pub enum Instruction {
         reward_duration: u64,
         pool_nonce: u8,
         fund_amount: u64,
+        pool_index: u64,
     },
     CreateUser {
         nonce: u8,
@@ -20,22 +21,62 @@ pub enum Instruction {
     ClosePool {},
     CloseUser {},
     FinalUnstake {},
+    MigratePool {},
+    MigrateUser {},
+    WindDownPool {},
+    ForceReturnStake {},
+    RecoverRewards {},
+    CheckPoolInvariants {},
+    AddRewardEpoch {
+        start_time: u64,
+        duration: u64,
+        amount: u64,
+    },
+    StartHalvingEmission {
+        halving_interval: u64,
+        amount: u64,
+    },
+    StartFixedApr {
+        apr_bps: u64,
+        duration: u64,
+        amount: u64,
+    },
+    ConfigureVesting {
+        vesting_period: u64,
+        penalty_bps: u64,
+    },
+    WithdrawVested {
+        instant: bool,
+    },
+    EnableReceiptToken {},
+    RedeemReceipt {
+        amount: u64,
+    },
+    TransferPosition {},
+    AcceptStakeMigrations {
+        accept: bool,
+    },
+    MigrateStake {},
 }
 
 impl Instruction {
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        Ok(match input[0] {
+        let (&tag, rest) = input.split_first().ok_or(InvalidInstruction)?;
+        Ok(match tag {
             0 => Self::InitializePool {
-                reward_duration: Self::unpack_to_u64(&input[1..9])?,
-                pool_nonce: input[9],
-                fund_amount: Self::unpack_to_u64(&input[10..18])?,
+                reward_duration: Self::unpack_to_u64(rest, 0)?,
+                pool_nonce: Self::unpack_to_u8(rest, 8)?,
+                fund_amount: Self::unpack_to_u64(rest, 9)?,
+                pool_index: Self::unpack_to_u64(rest, 17)?,
+            },
+            1 => Self::CreateUser {
+                nonce: Self::unpack_to_u8(rest, 0)?,
             },
-            1 => Self::CreateUser { nonce: input[1] },
             2 => Self::Stake {
-                amount_to_deposit: Self::unpack_to_u64(&input[1..9])?,
+                amount_to_deposit: Self::unpack_to_u64(rest, 0)?,
             },
             3 => Self::Unstake {
-                amount_to_withdraw: Self::unpack_to_u64(&input[1..9])?,
+                amount_to_withdraw: Self::unpack_to_u64(rest, 0)?,
             },
 
             4 => Self::ClaimRewards {},
@@ -46,16 +87,63 @@ impl Instruction {
 
             7 => Self::FinalUnstake {},
 
+            8 => Self::MigratePool {},
+
+            9 => Self::MigrateUser {},
+
+            10 => Self::WindDownPool {},
+
+            11 => Self::ForceReturnStake {},
+
+            12 => Self::RecoverRewards {},
+            13 => Self::CheckPoolInvariants {},
+            14 => Self::AddRewardEpoch {
+                start_time: Self::unpack_to_u64(rest, 0)?,
+                duration: Self::unpack_to_u64(rest, 8)?,
+                amount: Self::unpack_to_u64(rest, 16)?,
+            },
+            15 => Self::StartHalvingEmission {
+                halving_interval: Self::unpack_to_u64(rest, 0)?,
+                amount: Self::unpack_to_u64(rest, 8)?,
+            },
+            16 => Self::StartFixedApr {
+                apr_bps: Self::unpack_to_u64(rest, 0)?,
+                duration: Self::unpack_to_u64(rest, 8)?,
+                amount: Self::unpack_to_u64(rest, 16)?,
+            },
+            17 => Self::ConfigureVesting {
+                vesting_period: Self::unpack_to_u64(rest, 0)?,
+                penalty_bps: Self::unpack_to_u64(rest, 8)?,
+            },
+            18 => Self::WithdrawVested {
+                instant: Self::unpack_to_u8(rest, 0)? != 0,
+            },
+            19 => Self::EnableReceiptToken {},
+            20 => Self::RedeemReceipt {
+                amount: Self::unpack_to_u64(rest, 0)?,
+            },
+            21 => Self::TransferPosition {},
+            22 => Self::AcceptStakeMigrations {
+                accept: Self::unpack_to_u8(rest, 0)? != 0,
+            },
+            23 => Self::MigrateStake {},
+
             _ => return Err(InvalidInstruction.into()),
         })
     }
 
-    fn unpack_to_u64(input: &[u8]) -> Result<u64, ProgramError> {
+    fn unpack_to_u64(input: &[u8], offset: usize) -> Result<u64, ProgramError> {
         let out_value = input
-            .get(..8)
+            .get(offset..)
+            .and_then(|slice| slice.get(..8))
             .and_then(|slice| slice.try_into().ok())
             .map(u64::from_le_bytes)
             .ok_or(InvalidInstruction)?;
         Ok(out_value)
     }
-}
\ No newline at end of file
+
+    fn unpack_to_u8(input: &[u8], offset: usize) -> Result<u8, ProgramError> {
+        let out_value = input.get(offset).copied().ok_or(InvalidInstruction)?;
+        Ok(out_value)
+    }
+}
@@ -1,7 +1,9 @@
 pub mod error;
+pub mod event;
 pub mod instruction;
 pub mod processor;
 pub mod utils;
 pub mod state;
+pub mod token;
 #[cfg(not(feature = "no-entrypoint"))]
 pub mod entrypoint;
@@ -1,20 +1,46 @@
 use crate::instruction::Instruction;
 
 use {
-    claim_rewards::process_claim_rewards, close_pool::process_close_pool,
-    close_user::process_close_user, create_user::process_create_user,
-    final_unstake::process_final_unstake, initialize_pool::process_initialize_your_pool,
-    stake::process_stake, unstake::process_unstake,
+    accept_stake_migrations::process_accept_stake_migrations,
+    add_reward_epoch::process_add_reward_epoch,
+    check_pool_invariants::process_check_pool_invariants, claim_rewards::process_claim_rewards,
+    close_pool::process_close_pool, close_user::process_close_user,
+    configure_vesting::process_configure_vesting, create_user::process_create_user,
+    enable_receipt_token::process_enable_receipt_token, final_unstake::process_final_unstake,
+    force_return_stake::process_force_return_stake, initialize_pool::process_initialize_your_pool,
+    migrate_pool::process_migrate_pool, migrate_stake::process_migrate_stake,
+    migrate_user::process_migrate_user, recover_rewards::process_recover_rewards,
+    redeem_receipt::process_redeem_receipt, stake::process_stake,
+    start_fixed_apr::process_start_fixed_apr,
+    start_halving_emission::process_start_halving_emission,
+    transfer_position::process_transfer_position, unstake::process_unstake,
+    wind_down_pool::process_wind_down_pool, withdraw_vested::process_withdraw_vested,
 };
 
+pub mod accept_stake_migrations;
+pub mod add_reward_epoch;
+pub mod check_pool_invariants;
 pub mod claim_rewards;
 pub mod close_pool;
 pub mod close_user;
+pub mod configure_vesting;
 pub mod create_user;
+pub mod enable_receipt_token;
 pub mod final_unstake;
+pub mod force_return_stake;
 pub mod initialize_pool;
+pub mod migrate_pool;
+pub mod migrate_stake;
+pub mod migrate_user;
+pub mod recover_rewards;
+pub mod redeem_receipt;
 pub mod stake;
+pub mod start_fixed_apr;
+pub mod start_halving_emission;
+pub mod transfer_position;
 pub mod unstake;
+pub mod wind_down_pool;
+pub mod withdraw_vested;
 
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
 
@@ -31,6 +57,7 @@ impl Processor {
                 reward_duration,
                 pool_nonce,
                 fund_amount,
+                pool_index,
             } => {
                 msg!("Instruction::InitializePool");
                 process_initialize_your_pool(
@@ -38,6 +65,7 @@ impl Processor {
                     reward_duration,
                     pool_nonce,
                     fund_amount,
+                    pool_index,
                     program_id,
                 )
             }
@@ -75,6 +103,100 @@ impl Processor {
                 msg!("Instruction::FinalUnstake");
                 process_final_unstake(accounts, program_id)
             }
+
+            Instruction::MigratePool {} => {
+                msg!("Instruction::MigratePool");
+                process_migrate_pool(accounts, program_id)
+            }
+
+            Instruction::MigrateUser {} => {
+                msg!("Instruction::MigrateUser");
+                process_migrate_user(accounts, program_id)
+            }
+
+            Instruction::WindDownPool {} => {
+                msg!("Instruction::WindDownPool");
+                process_wind_down_pool(accounts, program_id)
+            }
+
+            Instruction::ForceReturnStake {} => {
+                msg!("Instruction::ForceReturnStake");
+                process_force_return_stake(accounts, program_id)
+            }
+
+            Instruction::RecoverRewards {} => {
+                msg!("Instruction::RecoverRewards");
+                process_recover_rewards(accounts, program_id)
+            }
+
+            Instruction::CheckPoolInvariants {} => {
+                msg!("Instruction::CheckPoolInvariants");
+                process_check_pool_invariants(accounts, program_id)
+            }
+
+            Instruction::AddRewardEpoch {
+                start_time,
+                duration,
+                amount,
+            } => {
+                msg!("Instruction::AddRewardEpoch");
+                process_add_reward_epoch(accounts, start_time, duration, amount, program_id)
+            }
+
+            Instruction::StartHalvingEmission {
+                halving_interval,
+                amount,
+            } => {
+                msg!("Instruction::StartHalvingEmission");
+                process_start_halving_emission(accounts, halving_interval, amount, program_id)
+            }
+
+            Instruction::StartFixedApr {
+                apr_bps,
+                duration,
+                amount,
+            } => {
+                msg!("Instruction::StartFixedApr");
+                process_start_fixed_apr(accounts, apr_bps, duration, amount, program_id)
+            }
+
+            Instruction::ConfigureVesting {
+                vesting_period,
+                penalty_bps,
+            } => {
+                msg!("Instruction::ConfigureVesting");
+                process_configure_vesting(accounts, vesting_period, penalty_bps, program_id)
+            }
+
+            Instruction::WithdrawVested { instant } => {
+                msg!("Instruction::WithdrawVested");
+                process_withdraw_vested(accounts, instant, program_id)
+            }
+
+            Instruction::EnableReceiptToken {} => {
+                msg!("Instruction::EnableReceiptToken");
+                process_enable_receipt_token(accounts, program_id)
+            }
+
+            Instruction::RedeemReceipt { amount } => {
+                msg!("Instruction::RedeemReceipt");
+                process_redeem_receipt(accounts, amount, program_id)
+            }
+
+            Instruction::TransferPosition {} => {
+                msg!("Instruction::TransferPosition");
+                process_transfer_position(accounts, program_id)
+            }
+
+            Instruction::AcceptStakeMigrations { accept } => {
+                msg!("Instruction::AcceptStakeMigrations");
+                process_accept_stake_migrations(accounts, accept, program_id)
+            }
+
+            Instruction::MigrateStake {} => {
+                msg!("Instruction::MigrateStake");
+                process_migrate_stake(accounts, program_id)
+            }
         }
     }
 }
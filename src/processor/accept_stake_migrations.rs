@@ -0,0 +1,41 @@
+use crate::{error::CustomError, state::YourPool};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Lets `MigrateStake` move stake into this pool from other pools of the same
+/// staking mint, or stops it again.
+///
+/// 0. `[signer]` Pool Owner Wallet Account
+/// 1. `[writable]` YOUR Pool Storage Account
+pub fn process_accept_stake_migrations(
+    accounts: &[AccountInfo],
+    accept: bool,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let pool_owner_wallet_account = next_account_info(account_info_iter)?;
+    let your_pool_storage_account = next_account_info(account_info_iter)?;
+
+    if !pool_owner_wallet_account.is_signer {
+        msg!("ProgramError::MissingRequiredSignature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
+    if your_pool_data.owner_wallet != *pool_owner_wallet_account.key {
+        msg!("CustomError::PoolOwnerMismatched");
+        return Err(CustomError::PoolOwnerMismatched.into());
+    }
+
+    msg!("accepts_stake_migrations: {}", accept);
+    your_pool_data.accepts_stake_migrations = accept as u8;
+    your_pool_data.save(your_pool_storage_account)?;
+
+    Ok(())
+}
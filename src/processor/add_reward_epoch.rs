@@ -0,0 +1,238 @@
+use crate::{
+    error::CustomError,
+    event::{StakingEvent, StakingEventKind},
+    processor::create_user::create_and_allocate_account_raw,
+    state::{
+        AccTypesWithVersion, RewardEpoch, RewardSchedule, YourPool, MAX_REWARD_EPOCHS,
+        REWARD_SCHEDULE_STORAGE_TOTAL_BYTES,
+    },
+    token, utils,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+    sysvar::Sysvar,
+};
+
+/// Funds a future reward epoch and appends it to the reward schedule of the
+/// pool, creating the schedule account on first use. The pool switches to the
+/// epoch's rate on its own once `start_time` has passed, see
+/// `utils::advance_reward_schedule`. Epochs have to start after the current
+/// reward period and every epoch already scheduled.
+///
+/// 0. `[signer, writable]` Pool Owner Wallet Account
+/// 1. `[writable]` YOUR Pool Storage Account
+/// 2. `[writable]` Reward Schedule Account (PDA)
+/// 3. `[writable]` YOUR Rewards Vault
+/// 4. `[writable]` YOUR ATA to Debit (Reward Token)
+/// 5. `[]` YOUR Rewards Mint
+/// 6. `[]` Rewards Token Program
+/// 7. `[]` System Program
+pub fn process_add_reward_epoch(
+    accounts: &[AccountInfo],
+    start_time: u64,
+    duration: u64,
+    amount: u64,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let pool_owner_wallet_account = next_account_info(account_info_iter)?;
+    let your_pool_storage_account = next_account_info(account_info_iter)?;
+    let reward_schedule_account = next_account_info(account_info_iter)?;
+    let your_rewards_vault = next_account_info(account_info_iter)?;
+    let your_rewards_ata_to_debit = next_account_info(account_info_iter)?;
+    let your_rewards_mint = next_account_info(account_info_iter)?;
+    let rewards_token_program = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if !pool_owner_wallet_account.is_signer {
+        msg!("ProgramError::MissingRequiredSignature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    token::check_token_program(rewards_token_program)?;
+    if *system_program_info.key != system_program::id() {
+        msg!("CustomError::InvalidSystemProgram");
+        return Err(CustomError::InvalidSystemProgram.into());
+    }
+    if amount == 0u64 {
+        msg!("CustomError::AmountMustBeGreaterThanZero");
+        return Err(CustomError::AmountMustBeGreaterThanZero.into());
+    }
+
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
+    if your_pool_data.owner_wallet != *pool_owner_wallet_account.key {
+        msg!("CustomError::PoolOwnerMismatched");
+        return Err(CustomError::PoolOwnerMismatched.into());
+    }
+    if your_pool_data.wind_down_date != 0 {
+        msg!("CustomError::PoolWindingDown");
+        return Err(CustomError::PoolWindingDown.into());
+    }
+
+    let (reward_schedule_address, bump_seed) =
+        get_reward_schedule_address_and_bump_seed(your_pool_storage_account.key, program_id);
+    if reward_schedule_address != *reward_schedule_account.key {
+        msg!("Error: Reward Schedule address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let mut reward_schedule_data = if reward_schedule_account.data_is_empty() {
+        create_and_allocate_account_raw(
+            *program_id,
+            reward_schedule_account,
+            system_program_info,
+            pool_owner_wallet_account,
+            REWARD_SCHEDULE_STORAGE_TOTAL_BYTES,
+            &[
+                REWARD_SCHEDULE_SEED,
+                &your_pool_storage_account.key.to_bytes(),
+                &[bump_seed],
+            ],
+        )?;
+        RewardSchedule {
+            acc_type: AccTypesWithVersion::RewardScheduleV1 as u8,
+            your_pool: *your_pool_storage_account.key,
+            next_epoch: 0,
+            epoch_count: 0,
+            epochs: [RewardEpoch::default(); MAX_REWARD_EPOCHS],
+        }
+    } else {
+        RewardSchedule::load(
+            reward_schedule_account,
+            program_id,
+            your_pool_storage_account.key,
+        )?
+    };
+
+    if your_rewards_vault.owner != rewards_token_program.key {
+        msg!("CustomError::AccountOwnerShouldBeTokenProgram");
+        return Err(CustomError::AccountOwnerShouldBeTokenProgram.into());
+    }
+    if your_pool_data.your_rewards_vault != *your_rewards_vault.key {
+        msg!("CustomError::InvalidRewardsVault");
+        return Err(CustomError::InvalidRewardsVault.into());
+    }
+    let your_rewards_vault_data = token::unpack_account(&your_rewards_vault.try_borrow_data()?)?;
+    let your_rewards_mint_data = token::unpack_vault_mint(
+        your_rewards_mint,
+        rewards_token_program,
+        &your_rewards_vault_data,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    utils::advance_reward_schedule(&mut your_pool_data, &mut reward_schedule_data, now)?;
+    utils::update_rewards(&mut your_pool_data, None, now)?;
+
+    let scheduled_end = reward_schedule_data
+        .last()
+        .map_or(0, |epoch| epoch.start_time.saturating_add(epoch.duration));
+    if start_time < now as u64
+        || start_time < your_pool_data.reward_duration_end
+        || start_time < scheduled_end
+    {
+        msg!("CustomError::RewardEpochOverlap");
+        return Err(CustomError::RewardEpochOverlap.into());
+    }
+    if start_time.checked_add(duration).is_none() {
+        msg!("CustomError::AmountOverflow");
+        return Err(CustomError::AmountOverflow.into());
+    }
+
+    msg!("Calling the token program to transfer YOUR rewards to Rewards Vault...");
+    let amount_received = token::deposit(
+        rewards_token_program,
+        your_rewards_ata_to_debit,
+        your_rewards_mint,
+        your_rewards_vault,
+        pool_owner_wallet_account,
+        amount,
+        your_rewards_mint_data.decimals,
+    )?;
+    let reward_rate = amount_received
+        .checked_div(duration)
+        .ok_or(CustomError::AmountOverflow)?;
+    msg!("reward_rate: {}", reward_rate);
+    if reward_rate == 0u64 {
+        msg!("CustomError::AmountMustBeGreaterThanZero");
+        return Err(CustomError::AmountMustBeGreaterThanZero.into());
+    }
+
+    reward_schedule_data.push(RewardEpoch {
+        start_time,
+        duration,
+        amount: amount_received,
+    })?;
+    your_pool_data.your_rewards_scheduled = your_pool_data
+        .your_rewards_scheduled
+        .checked_add(amount_received)
+        .ok_or(CustomError::AmountOverflow)?;
+    your_pool_data.next_epoch_start = reward_schedule_data
+        .next()
+        .map_or(0, |epoch| epoch.start_time);
+
+    reward_schedule_data.save(reward_schedule_account)?;
+    your_pool_data.stats.rewards_funded = your_pool_data
+        .stats
+        .rewards_funded
+        .saturating_add(amount_received);
+    your_pool_data.stats.last_activity_date = now;
+    your_pool_data.save(your_pool_storage_account)?;
+
+    StakingEvent {
+        kind: StakingEventKind::AddRewardEpoch,
+        pool: *your_pool_storage_account.key,
+        user: *pool_owner_wallet_account.key,
+        amount: amount_received,
+        balance_your_staked: 0u64,
+        unstake_pending: 0u64,
+        timestamp: now,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+/// Applies the scheduled epochs of `pool` that started by `now`. Instructions
+/// that accrue rewards take the Reward Schedule Account at a fixed position;
+/// it only has to be initialized once the pool schedules epochs.
+pub fn advance_pool_schedule(
+    pool: &mut YourPool,
+    pool_storage: &Pubkey,
+    reward_schedule_account: &AccountInfo,
+    program_id: &Pubkey,
+    now: i64,
+) -> ProgramResult {
+    if *reward_schedule_account.key != get_reward_schedule_address(pool_storage, program_id) {
+        msg!("Error: Reward Schedule address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if pool.next_epoch_start == 0 || pool.next_epoch_start > now as u64 {
+        return Ok(());
+    }
+    let mut reward_schedule_data =
+        RewardSchedule::load(reward_schedule_account, program_id, pool_storage)?;
+    utils::advance_reward_schedule(pool, &mut reward_schedule_data, now)?;
+    reward_schedule_data.save(reward_schedule_account)
+}
+
+pub const REWARD_SCHEDULE_SEED: &[u8] = b"reward_schedule";
+
+/// Derives the reward schedule account address of the given pool
+pub fn get_reward_schedule_address(pool_storage: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_reward_schedule_address_and_bump_seed(pool_storage, program_id).0
+}
+
+pub fn get_reward_schedule_address_and_bump_seed(
+    pool_storage: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REWARD_SCHEDULE_SEED, &pool_storage.to_bytes()],
+        program_id,
+    )
+}
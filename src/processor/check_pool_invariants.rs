@@ -0,0 +1,113 @@
+use crate::{
+    error::CustomError,
+    state::{AccTypesWithVersion, User, YourPool},
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::set_return_data,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Return data of `CheckPoolInvariants`: the pool counters next to what the
+/// supplied user records add up to.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PoolInvariantReport {
+    pub user_stake_count: u32,
+    pub users_counted: u32,
+    pub total_your_staked: u64,
+    pub staked_counted: u64,
+}
+
+impl PoolInvariantReport {
+    /// Records the pool counts that were not supplied, negative when more
+    /// records were supplied than the pool knows of.
+    pub fn user_count_drift(&self) -> i64 {
+        self.user_stake_count as i64 - self.users_counted as i64
+    }
+
+    /// Stake the pool counts that the supplied records do not hold.
+    pub fn staked_drift(&self) -> i128 {
+        self.total_your_staked as i128 - self.staked_counted as i128
+    }
+}
+
+/// Recounts the users of a pool and their stake from the supplied user
+/// storage accounts and reports how far the pool counters drifted from them.
+/// Read only; with every user record of the pool supplied both drifts are zero.
+/// V1 records count as users, their stake only joins the pool total once they
+/// went through `MigrateUser`.
+///
+/// 0. `[]` YOUR Pool Storage Account
+/// 1. `[]` User Storage Accounts, any number of them
+pub fn process_check_pool_invariants(
+    accounts: &[AccountInfo],
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let your_pool_storage_account = next_account_info(account_info_iter)?;
+
+    let your_pool_data = YourPool::load_any_version(your_pool_storage_account, program_id)?;
+
+    let mut counted: Vec<&Pubkey> = Vec::new();
+    let mut staked_counted = 0u64;
+    for user_storage_account in account_info_iter {
+        if counted.contains(&user_storage_account.key) {
+            msg!("Error: User Storage account supplied more than once");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let user_storage_data = User::load_any_version(
+            user_storage_account,
+            program_id,
+            your_pool_storage_account.key,
+        )?;
+        // The stored nonce spares a bump search per record
+        let user_storage_address = Pubkey::create_program_address(
+            &[
+                &user_storage_data.user_wallet.to_bytes(),
+                &your_pool_storage_account.key.to_bytes(),
+                &[user_storage_data.nonce],
+            ],
+            program_id,
+        )
+        .map_err(|_| ProgramError::InvalidSeeds)?;
+        if user_storage_address != *user_storage_account.key {
+            msg!("Error: User Storage address does not match seed derivation");
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if user_storage_data.acc_type == AccTypesWithVersion::UserDataV2 as u8 {
+            staked_counted = staked_counted
+                .checked_add(user_storage_data.balance_your_staked)
+                .ok_or(CustomError::AmountOverflow)?;
+        }
+        counted.push(user_storage_account.key);
+    }
+
+    let report = PoolInvariantReport {
+        user_stake_count: your_pool_data.user_stake_count,
+        users_counted: counted.len() as u32,
+        total_your_staked: your_pool_data.total_your_staked,
+        staked_counted,
+    };
+    msg!(
+        "user_stake_count: {}, users_counted: {}, drift: {}",
+        report.user_stake_count,
+        report.users_counted,
+        report.user_count_drift()
+    );
+    msg!(
+        "total_your_staked: {}, staked_counted: {}, drift: {}",
+        report.total_your_staked,
+        report.staked_counted,
+        report.staked_drift()
+    );
+    set_return_data(&report.try_to_vec()?);
+
+    Ok(())
+}
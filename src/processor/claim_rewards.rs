@@ -1,24 +1,21 @@
 use crate::{
     error::CustomError,
+    event::{StakingEvent, StakingEventKind},
+    processor::add_reward_epoch::advance_pool_schedule,
     processor::create_user::get_user_storage_address_and_bump_seed,
-    state::{
-        AccTypesWithVersion, User, YourPool, USER_STORAGE_TOTAL_BYTES,
-        YOUR_POOL_STORAGE_TOTAL_BYTES,
-    },
+    state::{User, YourPool},
+    token, utils,
 };
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
     program::invoke_signed,
     program_error::ProgramError,
-    program_pack::Pack,
     pubkey::Pubkey,
     sysvar::clock::Clock,
     sysvar::Sysvar,
 };
-use spl_token::state::Account as TokenAccount;
 
 pub fn process_claim_rewards(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
@@ -30,15 +27,14 @@ pub fn process_claim_rewards(accounts: &[AccountInfo], program_id: &Pubkey) -> P
     let user_rewards_ata = next_account_info(account_info_iter)?;
     let pool_signer_pda = next_account_info(account_info_iter)?;
     let token_program = next_account_info(account_info_iter)?;
+    let your_rewards_mint = next_account_info(account_info_iter)?;
+    let reward_schedule_account = next_account_info(account_info_iter)?;
 
     if !user_wallet_account.is_signer {
         msg!("ProgramError::MissingRequiredSignature");
         return Err(ProgramError::MissingRequiredSignature);
     }
-    if token_program.key != &spl_token::id() {
-        msg!("CustomError::InvalidTokenProgram");
-        return Err(CustomError::InvalidTokenProgram.into());
-    }
+    token::check_token_program(token_program)?;
 
     let (user_storage_address, _bump_seed) = get_user_storage_address_and_bump_seed(
         user_wallet_account.key,
@@ -50,85 +46,129 @@ pub fn process_claim_rewards(accounts: &[AccountInfo], program_id: &Pubkey) -> P
         return Err(ProgramError::InvalidSeeds);
     }
 
-    if your_pool_storage_account.data_len() != YOUR_POOL_STORAGE_TOTAL_BYTES {
-        msg!("CustomError::DataSizeNotMatched");
-        return Err(CustomError::DataSizeNotMatched.into());
-    }
-    let mut your_pool_data_byte_array = your_pool_storage_account.data.try_borrow_mut().unwrap();
-    let your_pool_data: YourPool =
-        YourPool::try_from_slice(&your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES])
-            .unwrap();
-    if your_pool_data.acc_type != AccTypesWithVersion::YourPoolDataV1 as u8 {
-        msg!("CustomError::ExpectedAccountTypeMismatched");
-        return Err(CustomError::ExpectedAccountTypeMismatched.into());
-    }
-
-    if user_storage_account.data_len() != USER_STORAGE_TOTAL_BYTES {
-        msg!("CustomError::DataSizeNotMatched");
-        return Err(CustomError::DataSizeNotMatched.into());
-    }
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
 
-    let mut user_data_byte_array = user_storage_account.data.try_borrow_mut().unwrap();
-    let mut user_storage_data: User =
-        User::try_from_slice(&user_data_byte_array[0usize..USER_STORAGE_TOTAL_BYTES]).unwrap();
-    if user_storage_data.acc_type != AccTypesWithVersion::UserDataV1 as u8 {
-        msg!("CustomError::ExpectedAccountTypeMismatched");
-        return Err(CustomError::ExpectedAccountTypeMismatched.into());
-    }
-
-    if user_storage_data.user_wallet != *user_wallet_account.key {
-        msg!("CustomError::UserStorageAuthorityMismatched");
-        return Err(CustomError::UserStorageAuthorityMismatched.into());
-    }
-    if user_storage_data.your_pool != *your_pool_storage_account.key {
-        msg!("CustomError::UserPoolMismatched");
-        return Err(CustomError::UserPoolMismatched.into());
-    }
+    let mut user_storage_data = User::load_for_wallet(
+        user_storage_account,
+        program_id,
+        user_wallet_account.key,
+        your_pool_storage_account.key,
+    )?;
 
-    if your_staking_vault.owner != token_program.key {
+    if !token::is_token_program(your_staking_vault.owner)
+        || your_rewards_vault.owner != token_program.key
+    {
         msg!("CustomError::AccountOwnerShouldBeTokenProgram");
         return Err(CustomError::AccountOwnerShouldBeTokenProgram.into());
     }
 
-    let your_staking_vault_data = TokenAccount::unpack(&your_staking_vault.data.borrow())?;
-    let (pool_signer_address, bump_seed) =
-        Pubkey::find_program_address(&[&your_pool_storage_account.key.to_bytes()], program_id);
+    let your_staking_vault_data = token::unpack_account(&your_staking_vault.try_borrow_data()?)?;
+    let pool_signer_address = your_pool_data.check_pool_signer(
+        pool_signer_pda,
+        your_pool_storage_account.key,
+        program_id,
+    )?;
 
-    if your_staking_vault_data.owner != pool_signer_address {
+    if your_staking_vault_data.owner != pool_signer_address
+        || your_pool_data.your_staking_vault != *your_staking_vault.key
+    {
         msg!("CustomError::InvalidStakingVault");
         return Err(CustomError::InvalidStakingVault.into());
     }
 
-    let now = Clock::get()?.unix_timestamp as i64;
+    let your_rewards_vault_data = token::unpack_account(&your_rewards_vault.try_borrow_data()?)?;
+    if your_rewards_vault_data.owner != pool_signer_address
+        || your_pool_data.your_rewards_vault != *your_rewards_vault.key
+    {
+        msg!("CustomError::InvalidRewardsVault");
+        return Err(CustomError::InvalidRewardsVault.into());
+    }
+    let your_rewards_mint_data =
+        token::unpack_vault_mint(your_rewards_mint, token_program, &your_rewards_vault_data)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    advance_pool_schedule(
+        &mut your_pool_data,
+        your_pool_storage_account.key,
+        reward_schedule_account,
+        program_id,
+        now,
+    )?;
+    utils::update_rewards(&mut your_pool_data, Some(&mut user_storage_data), now)?;
+    let reward_amount = user_storage_data.reward_per_token_pending;
+    if reward_amount == 0u64 {
+        msg!("CustomError::AmountMustBeGreaterThanZero");
+        return Err(CustomError::AmountMustBeGreaterThanZero.into());
+    }
     if user_storage_data.claim_timeout_date <= now || user_storage_data.claim_timeout_date == 0 {
-        msg!("Calling the token program to transfer YOUR to User from Rewards Vault...");
-        invoke_signed(
-            &spl_token::instruction::transfer(
-                token_program.key,
-                your_rewards_vault.key,
-                user_rewards_ata.key,
-                &pool_signer_address,
-                &[&pool_signer_address],
-                2,
-            )?,
-            &[
-                your_rewards_vault.clone(),
-                user_rewards_ata.clone(),
-                pool_signer_pda.clone(),
-                token_program.clone(),
-            ],
-            &[&[&your_pool_storage_account.key.to_bytes(), &[bump_seed]]],
-        )?;
+        if your_pool_data.vesting_period != 0 {
+            // Stays in the vault until `WithdrawVested`
+            utils::vest_rewards(
+                &mut user_storage_data,
+                reward_amount,
+                your_pool_data.vesting_period,
+                now,
+            )?;
+            your_pool_data.your_rewards_vesting = your_pool_data
+                .your_rewards_vesting
+                .checked_add(reward_amount)
+                .ok_or(CustomError::AmountOverflow)?;
+        } else {
+            msg!("Calling the token program to transfer YOUR to User from Rewards Vault...");
+            invoke_signed(
+                &token::transfer_checked(
+                    token_program.key,
+                    your_rewards_vault.key,
+                    your_rewards_mint.key,
+                    user_rewards_ata.key,
+                    &pool_signer_address,
+                    reward_amount,
+                    your_rewards_mint_data.decimals,
+                )?,
+                &[
+                    your_rewards_vault.clone(),
+                    your_rewards_mint.clone(),
+                    user_rewards_ata.clone(),
+                    pool_signer_pda.clone(),
+                    token_program.clone(),
+                ],
+                &[&[
+                    &your_pool_storage_account.key.to_bytes(),
+                    &[your_pool_data.pda_nonce],
+                ]],
+            )?;
+        }
 
-        user_storage_data.claim_timeout_date = now + 86400; // in seconds
+        user_storage_data.reward_per_token_pending = 0u64;
+        your_pool_data.stats.rewards_claimed = your_pool_data
+            .stats
+            .rewards_claimed
+            .saturating_add(reward_amount);
+        your_pool_data.your_rewards_owed = your_pool_data
+            .your_rewards_owed
+            .checked_sub(reward_amount)
+            .ok_or(CustomError::AmountOverflow)?;
+        user_storage_data.claim_timeout_date = now
+            .checked_add(86400) // in seconds
+            .ok_or(CustomError::AmountOverflow)?;
     } else {
         msg!("CustomError::UserClaimRewardTimeout");
         return Err(CustomError::UserClaimRewardTimeout.into());
     }
 
-    your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&your_pool_data.try_to_vec().unwrap());
-    user_data_byte_array[0usize..USER_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&user_storage_data.try_to_vec().unwrap());
+    your_pool_data.stats.last_activity_date = now;
+    your_pool_data.save(your_pool_storage_account)?;
+    user_storage_data.save(user_storage_account)?;
+
+    StakingEvent {
+        kind: StakingEventKind::ClaimRewards,
+        pool: *your_pool_storage_account.key,
+        user: *user_wallet_account.key,
+        amount: reward_amount,
+        balance_your_staked: user_storage_data.balance_your_staked,
+        unstake_pending: user_storage_data.unstake_pending,
+        timestamp: now,
+    }
+    .emit()?;
     Ok(())
 }
@@ -1,21 +1,21 @@
 use crate::{
     error::CustomError,
-    state::{AccTypesWithVersion, YourPool, YOUR_POOL_STORAGE_TOTAL_BYTES},
+    event::{StakingEvent, StakingEventKind},
+    processor::add_reward_epoch::get_reward_schedule_address,
+    state::{RewardSchedule, YourPool},
+    token, utils,
 };
 use solana_program::sysvar::clock::Clock;
 use solana_program::sysvar::Sysvar;
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
     program::invoke_signed,
     program_error::ProgramError,
-    program_pack::Pack,
     pubkey::Pubkey,
 };
-use spl_token::state::Account as TokenAccount;
 
 pub fn process_close_pool(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
@@ -27,28 +27,26 @@ pub fn process_close_pool(accounts: &[AccountInfo], program_id: &Pubkey) -> Prog
     let your_pool_storage_account = next_account_info(account_info_iter)?;
     let pool_signer_pda = next_account_info(account_info_iter)?;
     let token_program = next_account_info(account_info_iter)?;
+    let your_staking_mint = next_account_info(account_info_iter)?;
+    let your_rewards_mint = next_account_info(account_info_iter)?;
+    let rewards_token_program = next_account_info(account_info_iter)?;
+    let reward_schedule_account = next_account_info(account_info_iter)?;
 
     if !pool_owner_wallet_account.is_signer {
         msg!("ProgramError::MissingRequiredSignature");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if token_program.key != &spl_token::id() {
-        msg!("CustomError::InvalidTokenProgram");
-        return Err(CustomError::InvalidTokenProgram.into());
-    }
+    token::check_token_program(token_program)?;
+    token::check_token_program(rewards_token_program)?;
 
-    if your_pool_storage_account.data_len() != YOUR_POOL_STORAGE_TOTAL_BYTES {
-        msg!("CustomError::DataSizeNotMatched");
-        return Err(CustomError::DataSizeNotMatched.into());
-    }
-    let mut your_pool_data_byte_array = your_pool_storage_account.data.try_borrow_mut().unwrap();
-    let mut your_pool_data: YourPool =
-        YourPool::try_from_slice(&your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES])
-            .unwrap();
-    if your_pool_data.acc_type != AccTypesWithVersion::YourPoolDataV1 as u8 {
-        msg!("CustomError::ExpectedAccountTypeMismatched");
-        return Err(CustomError::ExpectedAccountTypeMismatched.into());
+    let your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
+
+    if get_reward_schedule_address(your_pool_storage_account.key, program_id)
+        != *reward_schedule_account.key
+    {
+        msg!("Error: Reward Schedule address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
     }
 
     if your_pool_data.owner_wallet != *pool_owner_wallet_account.key {
@@ -56,14 +54,19 @@ pub fn process_close_pool(accounts: &[AccountInfo], program_id: &Pubkey) -> Prog
         return Err(CustomError::PoolOwnerMismatched.into());
     }
 
-    if your_staking_vault.owner != token_program.key {
+    if your_staking_vault.owner != token_program.key
+        || your_rewards_vault.owner != rewards_token_program.key
+    {
         msg!("CustomError::AccountOwnerShouldBeTokenProgram");
         return Err(CustomError::AccountOwnerShouldBeTokenProgram.into());
     }
 
-    let your_staking_vault_data = TokenAccount::unpack(&your_staking_vault.data.borrow())?;
-    let (pool_signer_address, bump_seed) =
-        Pubkey::find_program_address(&[&your_pool_storage_account.key.to_bytes()], program_id);
+    let your_staking_vault_data = token::unpack_account(&your_staking_vault.try_borrow_data()?)?;
+    let pool_signer_address = your_pool_data.check_pool_signer(
+        pool_signer_pda,
+        your_pool_storage_account.key,
+        program_id,
+    )?;
 
     if your_staking_vault_data.owner != pool_signer_address
         || your_pool_data.your_staking_vault != *your_staking_vault.key
@@ -76,65 +79,90 @@ pub fn process_close_pool(accounts: &[AccountInfo], program_id: &Pubkey) -> Prog
 
     let now = Clock::get()?.unix_timestamp;
 
-    if your_pool_data.reward_duration_end <= 0u64
+    if your_pool_data.reward_duration_end == 0u64
         || your_pool_data.reward_duration_end >= (now as u64)
         || your_pool_data.user_stake_count != 0u32
         || total_your_staked != 0u64
+        || your_pool_data.next_epoch_start != 0u64
     {
         msg!("CustomError::PoolStillActive");
         return Err(CustomError::PoolStillActive.into());
     }
 
+    let your_staking_mint_data =
+        token::unpack_vault_mint(your_staking_mint, token_program, &your_staking_vault_data)?;
+
     msg!("Calling the token program to transfer YOUR to Staking Refund from Staking Vault...");
     invoke_signed(
-        &spl_token::instruction::transfer(
+        &token::transfer_checked(
             token_program.key,
             your_staking_vault.key,
+            your_staking_mint.key,
             your_staking_refund_ata.key,
             &pool_signer_address,
-            &[&pool_signer_address],
             your_staking_vault_data.amount,
+            your_staking_mint_data.decimals,
         )?,
         &[
             your_staking_vault.clone(),
+            your_staking_mint.clone(),
             your_staking_refund_ata.clone(),
             pool_signer_pda.clone(),
             token_program.clone(),
         ],
-        &[&[&your_pool_storage_account.key.to_bytes()[..], &[bump_seed]]],
+        &[&[
+            &your_pool_storage_account.key.to_bytes()[..],
+            &[your_pool_data.pda_nonce],
+        ]],
     )?;
 
-    let your_rewards_vault_data = TokenAccount::unpack(&your_rewards_vault.data.borrow())?;
-    let (pool_signer_address, bump_seed) =
-        Pubkey::find_program_address(&[&your_pool_storage_account.key.to_bytes()], program_id);
+    let your_rewards_vault_data = token::unpack_account(&your_rewards_vault.try_borrow_data()?)?;
+    if your_rewards_vault_data.owner != pool_signer_address
+        || your_pool_data.your_rewards_vault != *your_rewards_vault.key
+    {
+        msg!("CustomError::InvalidRewardsVault");
+        return Err(CustomError::InvalidRewardsVault.into());
+    }
+    let your_rewards_mint_data = token::unpack_vault_mint(
+        your_rewards_mint,
+        rewards_token_program,
+        &your_rewards_vault_data,
+    )?;
 
     msg!("Calling the token program to transfer YOUR to Rewards Refund from Rewards Vault...");
     invoke_signed(
-        &spl_token::instruction::transfer(
-            token_program.key,
+        &token::transfer_checked(
+            rewards_token_program.key,
             your_rewards_vault.key,
+            your_rewards_mint.key,
             your_rewards_refund_ata.key,
             &pool_signer_address,
-            &[&pool_signer_address],
             your_rewards_vault_data.amount,
+            your_rewards_mint_data.decimals,
         )?,
         &[
             your_rewards_vault.clone(),
+            your_rewards_mint.clone(),
             your_rewards_refund_ata.clone(),
             pool_signer_pda.clone(),
-            token_program.clone(),
+            rewards_token_program.clone(),
         ],
-        &[&[&your_pool_storage_account.key.to_bytes()[..], &[bump_seed]]],
+        &[&[
+            &your_pool_storage_account.key.to_bytes()[..],
+            &[your_pool_data.pda_nonce],
+        ]],
     )?;
 
+    token::harvest_withheld_tokens(token_program, your_staking_mint, your_staking_vault)?;
+    token::harvest_withheld_tokens(rewards_token_program, your_rewards_mint, your_rewards_vault)?;
+
     msg!("Calling the token program to close YOUR Staking Vault...");
     invoke_signed(
-        &spl_token::instruction::close_account(
+        &token::close_account(
             token_program.key,
             your_staking_vault.key,
             pool_owner_wallet_account.key,
             &pool_signer_address,
-            &[&pool_signer_address],
         )?,
         &[
             your_staking_vault.clone(),
@@ -142,29 +170,61 @@ pub fn process_close_pool(accounts: &[AccountInfo], program_id: &Pubkey) -> Prog
             pool_signer_pda.clone(),
             token_program.clone(),
         ],
-        &[&[&your_pool_storage_account.key.to_bytes()[..], &[bump_seed]]],
+        &[&[
+            &your_pool_storage_account.key.to_bytes()[..],
+            &[your_pool_data.pda_nonce],
+        ]],
     )?;
 
     msg!("Calling the token program to close YOUR Rewards Vault...");
     invoke_signed(
-        &spl_token::instruction::close_account(
-            token_program.key,
+        &token::close_account(
+            rewards_token_program.key,
             your_rewards_vault.key,
             pool_owner_wallet_account.key,
             &pool_signer_address,
-            &[&pool_signer_address],
         )?,
         &[
             your_rewards_vault.clone(),
             pool_owner_wallet_account.clone(),
             pool_signer_pda.clone(),
-            token_program.clone(),
+            rewards_token_program.clone(),
         ],
-        &[&[&your_pool_storage_account.key.to_bytes()[..], &[bump_seed]]],
+        &[&[
+            &your_pool_storage_account.key.to_bytes()[..],
+            &[your_pool_data.pda_nonce],
+        ]],
     )?;
 
-    your_pool_data.your_staking_vault = Pubkey::default();
-    your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&your_pool_data.try_to_vec().unwrap());
+    // Pools that never scheduled an epoch have no schedule to close
+    if reward_schedule_account.owner == program_id && !reward_schedule_account.data_is_empty() {
+        RewardSchedule::load(
+            reward_schedule_account,
+            program_id,
+            your_pool_storage_account.key,
+        )?;
+        msg!(
+            "Closing the Reward Schedule account and transferring lamports to Pool owner wallet..."
+        );
+        utils::close_account(
+            reward_schedule_account,
+            pool_owner_wallet_account,
+            &mut reward_schedule_account.try_borrow_mut_data()?,
+        )?;
+    }
+
+    msg!("Closing the Pool Storage account and transferring lamports to Pool owner wallet...");
+    utils::close_pool_account(your_pool_storage_account, pool_owner_wallet_account)?;
+
+    StakingEvent {
+        kind: StakingEventKind::ClosePool,
+        pool: *your_pool_storage_account.key,
+        user: *pool_owner_wallet_account.key,
+        amount: your_rewards_vault_data.amount,
+        balance_your_staked: 0u64,
+        unstake_pending: 0u64,
+        timestamp: now,
+    }
+    .emit()?;
     Ok(())
 }
@@ -1,26 +1,27 @@
 use crate::{
     error::CustomError,
+    event::{StakingEvent, StakingEventKind},
+    processor::add_reward_epoch::advance_pool_schedule,
     processor::create_user::get_user_storage_address_and_bump_seed,
-    state::{
-        AccTypesWithVersion, User, YourPool, USER_STORAGE_TOTAL_BYTES,
-        YOUR_POOL_STORAGE_TOTAL_BYTES,
-    },
+    state::{User, YourPool},
     utils,
 };
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
     pubkey::Pubkey,
+    sysvar::Sysvar,
 };
 
-use borsh::{BorshDeserialize, BorshSerialize};
 pub fn process_close_user(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let user_wallet_account = next_account_info(account_info_iter)?;
     let user_storage_account = next_account_info(account_info_iter)?;
     let your_pool_storage_account = next_account_info(account_info_iter)?;
+    let reward_schedule_account = next_account_info(account_info_iter)?;
 
     if !user_wallet_account.is_signer {
         msg!("ProgramError::MissingRequiredSignature");
@@ -37,52 +38,58 @@ pub fn process_close_user(accounts: &[AccountInfo], program_id: &Pubkey) -> Prog
         return Err(ProgramError::InvalidSeeds);
     }
 
-    if your_pool_storage_account.data_len() != YOUR_POOL_STORAGE_TOTAL_BYTES {
-        msg!("CustomError::DataSizeNotMatched");
-        return Err(CustomError::DataSizeNotMatched.into());
-    }
-    let mut your_pool_data_byte_array = your_pool_storage_account.data.try_borrow_mut().unwrap();
-    let mut your_pool_data: YourPool =
-        YourPool::try_from_slice(&your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES])
-            .unwrap();
-    if your_pool_data.acc_type != AccTypesWithVersion::YourPoolDataV1 as u8 {
-        msg!("CustomError::ExpectedAccountTypeMismatched");
-        return Err(CustomError::ExpectedAccountTypeMismatched.into());
-    }
-
-    your_pool_data.user_stake_count -= 1u32;
-
-    your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&your_pool_data.try_to_vec().unwrap());
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
 
-    if user_storage_account.data_len() != USER_STORAGE_TOTAL_BYTES {
-        msg!("CustomError::DataSizeNotMatched");
-        return Err(CustomError::DataSizeNotMatched.into());
-    }
+    let mut user_storage_data = User::load_for_wallet(
+        user_storage_account,
+        program_id,
+        user_wallet_account.key,
+        your_pool_storage_account.key,
+    )?;
 
-    let mut user_data_byte_array = user_storage_account.data.try_borrow_mut().unwrap();
-    let user_storage_data: User =
-        User::try_from_slice(&user_data_byte_array[0usize..USER_STORAGE_TOTAL_BYTES]).unwrap();
-    if user_storage_data.acc_type != AccTypesWithVersion::UserDataV1 as u8 {
-        msg!("CustomError::ExpectedAccountTypeMismatched");
-        return Err(CustomError::ExpectedAccountTypeMismatched.into());
+    // Closing the record would forfeit whatever it still holds in the vaults
+    let now = Clock::get()?.unix_timestamp;
+    advance_pool_schedule(
+        &mut your_pool_data,
+        your_pool_storage_account.key,
+        reward_schedule_account,
+        program_id,
+        now,
+    )?;
+    utils::update_rewards(&mut your_pool_data, Some(&mut user_storage_data), now)?;
+    if user_storage_data.balance_your_staked != 0u64
+        || user_storage_data.unstake_pending != 0u64
+        || user_storage_data.reward_per_token_pending != 0u64
+        || user_storage_data.vesting_locked != 0u64
+        || user_storage_data.vesting_unlocked != 0u64
+    {
+        msg!("CustomError::UserPositionNotEmpty");
+        return Err(CustomError::UserPositionNotEmpty.into());
     }
 
-    if user_storage_data.user_wallet != *user_wallet_account.key {
-        msg!("CustomError::UserStorageAuthorityMismatched");
-        return Err(CustomError::UserStorageAuthorityMismatched.into());
-    }
-    if user_storage_data.your_pool != *your_pool_storage_account.key {
-        msg!("CustomError::UserPoolMismatched");
-        return Err(CustomError::UserPoolMismatched.into());
-    }
+    your_pool_data.user_stake_count = your_pool_data
+        .user_stake_count
+        .checked_sub(1u32)
+        .ok_or(CustomError::AmountOverflow)?;
+    your_pool_data.stats.last_activity_date = now;
+    your_pool_data.save(your_pool_storage_account)?;
 
     msg!("Closing the User Data Storage account and transferring lamports to User wallet...");
     utils::close_account(
         user_storage_account,
         user_wallet_account,
-        &mut user_data_byte_array,
-    )
-    .unwrap();
+        &mut user_storage_account.try_borrow_mut_data()?,
+    )?;
+
+    StakingEvent {
+        kind: StakingEventKind::CloseUser,
+        pool: *your_pool_storage_account.key,
+        user: *user_wallet_account.key,
+        amount: 0u64,
+        balance_your_staked: user_storage_data.balance_your_staked,
+        unstake_pending: user_storage_data.unstake_pending,
+        timestamp: now,
+    }
+    .emit()?;
     Ok(())
 }
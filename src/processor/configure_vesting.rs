@@ -0,0 +1,49 @@
+use crate::{error::CustomError, state::YourPool, utils::constants::BPS_DENOMINATOR};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Sets how long claimed rewards vest and what share of the locked part an
+/// instant `WithdrawVested` forfeits. A `vesting_period` of 0 turns vesting
+/// off for later claims; what already vests keeps its schedule.
+///
+/// 0. `[signer]` Pool Owner Wallet Account
+/// 1. `[writable]` YOUR Pool Storage Account
+pub fn process_configure_vesting(
+    accounts: &[AccountInfo],
+    vesting_period: u64,
+    penalty_bps: u64,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let pool_owner_wallet_account = next_account_info(account_info_iter)?;
+    let your_pool_storage_account = next_account_info(account_info_iter)?;
+
+    if !pool_owner_wallet_account.is_signer {
+        msg!("ProgramError::MissingRequiredSignature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if penalty_bps > BPS_DENOMINATOR {
+        msg!("CustomError::InvalidVestingPenalty");
+        return Err(CustomError::InvalidVestingPenalty.into());
+    }
+
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
+    if your_pool_data.owner_wallet != *pool_owner_wallet_account.key {
+        msg!("CustomError::PoolOwnerMismatched");
+        return Err(CustomError::PoolOwnerMismatched.into());
+    }
+
+    msg!("vesting_period: {}", vesting_period);
+    msg!("vesting_penalty_bps: {}", penalty_bps);
+    your_pool_data.vesting_period = vesting_period;
+    your_pool_data.vesting_penalty_bps = penalty_bps;
+    your_pool_data.save(your_pool_storage_account)?;
+
+    Ok(())
+}
@@ -1,10 +1,6 @@
 use crate::{
     error::CustomError,
-    state,
-    state::{
-        AccTypesWithVersion, User, YourPool, USER_STORAGE_TOTAL_BYTES,
-        YOUR_POOL_STORAGE_TOTAL_BYTES,
-    },
+    state::{AccTypesWithVersion, User, YourPool, USER_STORAGE_TOTAL_BYTES},
 };
 
 use solana_program::{
@@ -16,12 +12,10 @@ use solana_program::{
     program_error::ProgramError,
     pubkey::Pubkey,
     system_instruction, system_program,
-    sysvar::{rent::Rent, Sysvar},
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
 use std::convert::TryInto;
 
-use borsh::{BorshDeserialize, BorshSerialize};
-
 pub fn process_create_user(
     accounts: &[AccountInfo],
     nonce: u8,
@@ -61,6 +55,8 @@ pub fn process_create_user(
         return Err(ProgramError::InvalidSeeds);
     }
 
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
+
     let user_storage_account_signer_seeds: &[&[_]] = &[
         &user_wallet_account.key.to_bytes(),
         &your_pool_storage_account.key.to_bytes(),
@@ -72,13 +68,17 @@ pub fn process_create_user(
         user_storage_account,
         system_program_info,
         user_wallet_account,
-        state::USER_STORAGE_TOTAL_BYTES,
+        USER_STORAGE_TOTAL_BYTES,
         user_storage_account_signer_seeds,
-    )
-    .unwrap();
+    )?;
+
+    if user_storage_account.data_len() != USER_STORAGE_TOTAL_BYTES {
+        msg!("CustomError::DataSizeNotMatched");
+        return Err(CustomError::DataSizeNotMatched.into());
+    }
 
     let user_storage_data = User {
-        acc_type: state::AccTypesWithVersion::UserDataV1 as u8,
+        acc_type: AccTypesWithVersion::UserDataV2 as u8,
         user_wallet: *user_wallet_account.key,
         your_pool: *your_pool_storage_account.key,
         balance_your_staked: 0u64,
@@ -86,30 +86,24 @@ pub fn process_create_user(
         unstake_pending_date: 0i64,
         nonce: bump_seed,
         claim_timeout_date: 0i64,
+        reward_per_token_complete: your_pool_data.reward_per_token_stored,
+        reward_per_token_pending: 0u64,
+        vesting_locked: 0u64,
+        vesting_start: 0i64,
+        vesting_end: 0i64,
+        vesting_unlocked: 0u64,
+        reserved: [0u8; 102],
     };
 
-    let mut user_data_byte_array = user_storage_account.data.try_borrow_mut().unwrap();
+    user_storage_data.save(user_storage_account)?;
 
-    user_data_byte_array[0usize..USER_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&user_storage_data.try_to_vec().unwrap());
+    your_pool_data.user_stake_count = your_pool_data
+        .user_stake_count
+        .checked_add(1u32)
+        .ok_or(CustomError::AmountOverflow)?;
+    your_pool_data.stats.last_activity_date = Clock::get()?.unix_timestamp;
 
-    if your_pool_storage_account.data_len() != YOUR_POOL_STORAGE_TOTAL_BYTES {
-        msg!("CustomError::DataSizeNotMatched");
-        return Err(CustomError::DataSizeNotMatched.into());
-    }
-    let mut your_pool_data_byte_array = your_pool_storage_account.data.try_borrow_mut().unwrap();
-    let mut your_pool_data: YourPool =
-        YourPool::try_from_slice(&your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES])
-            .unwrap();
-    if your_pool_data.acc_type != AccTypesWithVersion::YourPoolDataV1 as u8 {
-        msg!("CustomError::ExpectedAccountTypeMismatched");
-        return Err(CustomError::ExpectedAccountTypeMismatched.into());
-    }
-
-    your_pool_data.user_stake_count += 1u32;
-
-    your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&your_pool_data.try_to_vec().unwrap());
+    your_pool_data.save(your_pool_storage_account)?;
 
     Ok(())
 }
@@ -159,12 +153,41 @@ pub fn create_and_allocate_account_raw<'a>(
     Ok(())
 }
 
+/// Grows a program-owned account to `size` in place, topping its lamports up
+/// to the rent exempt minimum from `payer_info` first.
+pub fn realloc_account_raw<'a>(
+    account_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    size: usize,
+) -> Result<(), ProgramError> {
+    let rent = Rent::get()?;
+    let required_lamports = rent
+        .minimum_balance(size)
+        .saturating_sub(account_info.lamports());
+
+    if required_lamports > 0 {
+        msg!("Transfer {} lamports to the account", required_lamports);
+        invoke(
+            &system_instruction::transfer(payer_info.key, account_info.key, required_lamports),
+            &[
+                payer_info.clone(),
+                account_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+    }
+
+    msg!("Reallocate the account");
+    account_info.realloc(size, true)
+}
+
 pub fn assert_derivation(
     program_id: &Pubkey,
     account: &AccountInfo,
     path: &[&[u8]],
 ) -> Result<u8, ProgramError> {
-    let (key, bump) = Pubkey::find_program_address(&path, program_id);
+    let (key, bump) = Pubkey::find_program_address(path, program_id);
     if key != *account.key {
         return Err(CustomError::DerivedKeyInvalid.into());
     }
@@ -0,0 +1,189 @@
+use crate::{
+    error::CustomError,
+    processor::create_user::create_and_allocate_account_raw,
+    processor::initialize_pool::create_vault,
+    processor::redeem_receipt::{
+        get_receipt_escrow_authority_and_bump_seed, RECEIPT_STAKE_ESCROW_SEED,
+    },
+    state::YourPool,
+    token,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use spl_token::state::Mint;
+
+/// Creates the receipt mint of the pool at a PDA of the pool storage, with
+/// the pool signer as mint authority and the decimals of the staking mint.
+/// From here on `Stake` mints receipts 1:1 for what it credits and
+/// `FinalUnstake` burns them. Also creates the receipt stake escrow that
+/// `ForceReturnStake` moves the stake to once the pool winds down, where any
+/// holder can redeem receipts with `RedeemReceipt`. Only possible before
+/// anyone joined the pool, so every position is backed by receipts.
+///
+/// 0. `[signer, writable]` Pool Owner Wallet Account
+/// 1. `[writable]` YOUR Pool Storage Account
+/// 2. `[writable]` Receipt Mint (PDA)
+/// 3. `[]` YOUR Staking Mint
+/// 4. `[]` Pool Signer PDA
+/// 5. `[]` Token Program
+/// 6. `[]` System Program
+/// 7. `[]` Rent Sysvar
+/// 8. `[writable]` Receipt Stake Escrow (PDA)
+/// 9. `[]` Receipt Escrow Authority (PDA)
+pub fn process_enable_receipt_token(
+    accounts: &[AccountInfo],
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let pool_owner_wallet_account = next_account_info(account_info_iter)?;
+    let your_pool_storage_account = next_account_info(account_info_iter)?;
+    let receipt_mint = next_account_info(account_info_iter)?;
+    let your_staking_mint = next_account_info(account_info_iter)?;
+    let pool_signer_pda = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+    let receipt_stake_escrow = next_account_info(account_info_iter)?;
+    let receipt_escrow_authority = next_account_info(account_info_iter)?;
+
+    if !pool_owner_wallet_account.is_signer {
+        msg!("ProgramError::MissingRequiredSignature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    token::check_token_program(token_program)?;
+    if *system_program_info.key != system_program::id() {
+        msg!("CustomError::InvalidSystemProgram");
+        return Err(CustomError::InvalidSystemProgram.into());
+    }
+    if *rent_sysvar_info.key != sysvar::rent::id() {
+        msg!("ProgramError::InvalidArgument");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
+    if your_pool_data.owner_wallet != *pool_owner_wallet_account.key {
+        msg!("CustomError::PoolOwnerMismatched");
+        return Err(CustomError::PoolOwnerMismatched.into());
+    }
+    if your_pool_data.wind_down_date != 0 {
+        msg!("CustomError::PoolWindingDown");
+        return Err(CustomError::PoolWindingDown.into());
+    }
+    if your_pool_data.has_receipt_token() {
+        msg!("CustomError::PoolAddressAlreadyInitialized");
+        return Err(CustomError::PoolAddressAlreadyInitialized.into());
+    }
+    if your_pool_data.user_stake_count != 0 || your_pool_data.total_your_staked != 0 {
+        msg!("CustomError::PoolHasUsers");
+        return Err(CustomError::PoolHasUsers.into());
+    }
+
+    if your_pool_data.your_staking_mint != *your_staking_mint.key
+        || your_staking_mint.owner != token_program.key
+    {
+        msg!("CustomError::MintMismatched");
+        return Err(CustomError::MintMismatched.into());
+    }
+    let your_staking_mint_data = token::unpack_mint(&your_staking_mint.try_borrow_data()?)?;
+    let pool_signer_address = your_pool_data.check_pool_signer(
+        pool_signer_pda,
+        your_pool_storage_account.key,
+        program_id,
+    )?;
+
+    let (receipt_mint_address, bump_seed) =
+        get_receipt_mint_address_and_bump_seed(your_pool_storage_account.key, program_id);
+    if receipt_mint_address != *receipt_mint.key {
+        msg!("Error: Receipt Mint address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    msg!("Creating the Receipt mint owned by Pool program...");
+    create_and_allocate_account_raw(
+        *token_program.key,
+        receipt_mint,
+        system_program_info,
+        pool_owner_wallet_account,
+        Mint::LEN,
+        &[
+            RECEIPT_MINT_SEED,
+            &your_pool_storage_account.key.to_bytes(),
+            &[bump_seed],
+        ],
+    )?;
+    invoke(
+        &token::initialize_mint(
+            token_program.key,
+            receipt_mint.key,
+            &pool_signer_address,
+            your_staking_mint_data.decimals,
+        )?,
+        &[
+            receipt_mint.clone(),
+            rent_sysvar_info.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    let (receipt_escrow_authority_address, _bump_seed) =
+        get_receipt_escrow_authority_and_bump_seed(your_pool_storage_account.key, program_id);
+    if receipt_escrow_authority_address != *receipt_escrow_authority.key {
+        msg!("CustomError::DerivedKeyInvalid");
+        return Err(CustomError::DerivedKeyInvalid.into());
+    }
+    msg!("Creating the Receipt Stake Escrow owned by the Receipt Escrow Authority...");
+    create_vault(
+        RECEIPT_STAKE_ESCROW_SEED,
+        receipt_stake_escrow,
+        your_staking_mint,
+        your_pool_storage_account,
+        pool_owner_wallet_account,
+        receipt_escrow_authority,
+        rent_sysvar_info,
+        system_program_info,
+        token_program,
+        program_id,
+    )?;
+
+    your_pool_data.receipt_mint = *receipt_mint.key;
+    your_pool_data.save(your_pool_storage_account)?;
+
+    Ok(())
+}
+
+/// Checks that `receipt_mint` is the receipt mint of the pool and returns its
+/// decimals.
+pub fn check_receipt_mint(
+    pool: &YourPool,
+    receipt_mint: &AccountInfo,
+    token_program: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    if pool.receipt_mint != *receipt_mint.key || receipt_mint.owner != token_program.key {
+        msg!("CustomError::InvalidReceiptMint");
+        return Err(CustomError::InvalidReceiptMint.into());
+    }
+    Ok(token::unpack_mint(&receipt_mint.try_borrow_data()?)?.decimals)
+}
+
+pub const RECEIPT_MINT_SEED: &[u8] = b"receipt_mint";
+
+/// Derives the receipt mint address of the given pool
+pub fn get_receipt_mint_address(pool_storage: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_receipt_mint_address_and_bump_seed(pool_storage, program_id).0
+}
+
+pub fn get_receipt_mint_address_and_bump_seed(
+    pool_storage: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RECEIPT_MINT_SEED, &pool_storage.to_bytes()], program_id)
+}
@@ -1,24 +1,39 @@
 use crate::{
     error::CustomError,
+    event::{StakingEvent, StakingEventKind},
+    processor::add_reward_epoch::advance_pool_schedule,
     processor::create_user::get_user_storage_address_and_bump_seed,
-    state::{
-        AccTypesWithVersion, User, YourPool, USER_STORAGE_TOTAL_BYTES,
-        YOUR_POOL_STORAGE_TOTAL_BYTES,
-    },
+    processor::enable_receipt_token::check_receipt_mint,
+    state::{User, YourPool},
+    token, utils,
 };
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
-    program::invoke_signed,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
     pubkey::Pubkey,
     sysvar::clock::Clock,
     sysvar::Sysvar,
 };
 
+/// Pays out the pending unstake of the user once it is due. Pools with a
+/// receipt token burn the same amount of receipts from the User Receipt
+/// Account, the receipts are the claim on the stake.
+///
+/// 0. `[signer]` User Wallet Account
+/// 1. `[writable]` User Storage Account
+/// 2. `[writable]` YOUR Pool Storage Account
+/// 3. `[writable]` YOUR Staking Vault
+/// 4. `[writable]` User YOUR ATA
+/// 5. `[]` Pool Signer PDA
+/// 6. `[]` Token Program
+/// 7. `[]` YOUR Staking Mint
+/// 8. `[writable]` Reward Schedule Account (PDA), see `advance_pool_schedule`
+/// 9. `[writable]` Receipt Mint, required by receipt token pools
+/// 10. `[writable]` User Receipt Account, required by receipt token pools
 pub fn process_final_unstake(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let user_wallet_account = next_account_info(account_info_iter)?;
@@ -28,15 +43,16 @@ pub fn process_final_unstake(accounts: &[AccountInfo], program_id: &Pubkey) -> P
     let user_your_ata = next_account_info(account_info_iter)?;
     let pool_signer_pda = next_account_info(account_info_iter)?;
     let token_program = next_account_info(account_info_iter)?;
+    let your_staking_mint = next_account_info(account_info_iter)?;
+    let reward_schedule_account = next_account_info(account_info_iter)?;
+    let receipt_mint = next_account_info(account_info_iter).ok();
+    let user_receipt_account = next_account_info(account_info_iter).ok();
 
     if !user_wallet_account.is_signer {
         msg!("ProgramError::MissingRequiredSignature");
         return Err(ProgramError::MissingRequiredSignature);
     }
-    if token_program.key != &spl_token::id() {
-        msg!("CustomError::InvalidTokenProgram");
-        return Err(CustomError::InvalidTokenProgram.into());
-    }
+    token::check_token_program(token_program)?;
 
     let (user_storage_address, _bump_seed) = get_user_storage_address_and_bump_seed(
         user_wallet_account.key,
@@ -48,78 +64,136 @@ pub fn process_final_unstake(accounts: &[AccountInfo], program_id: &Pubkey) -> P
         return Err(ProgramError::InvalidSeeds);
     }
 
-    if your_pool_storage_account.data_len() != YOUR_POOL_STORAGE_TOTAL_BYTES {
-        msg!("CustomError::DataSizeNotMatched");
-        return Err(CustomError::DataSizeNotMatched.into());
-    }
-    let mut your_pool_data_byte_array = your_pool_storage_account.data.try_borrow_mut().unwrap();
-    let your_pool_data: YourPool =
-        YourPool::try_from_slice(&your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES])
-            .unwrap();
-    if your_pool_data.acc_type != AccTypesWithVersion::YourPoolDataV1 as u8 {
-        msg!("CustomError::ExpectedAccountTypeMismatched");
-        return Err(CustomError::ExpectedAccountTypeMismatched.into());
-    }
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
 
-    if user_storage_account.data_len() != USER_STORAGE_TOTAL_BYTES {
-        msg!("CustomError::DataSizeNotMatched");
-        return Err(CustomError::DataSizeNotMatched.into());
-    }
+    let mut user_storage_data = User::load_for_wallet(
+        user_storage_account,
+        program_id,
+        user_wallet_account.key,
+        your_pool_storage_account.key,
+    )?;
 
-    let mut user_data_byte_array = user_storage_account.data.try_borrow_mut().unwrap();
-    let mut user_storage_data: User =
-        User::try_from_slice(&user_data_byte_array[0usize..USER_STORAGE_TOTAL_BYTES]).unwrap();
-    if user_storage_data.acc_type != AccTypesWithVersion::UserDataV1 as u8 {
-        msg!("CustomError::ExpectedAccountTypeMismatched");
-        return Err(CustomError::ExpectedAccountTypeMismatched.into());
-    }
+    let pool_signer_address = your_pool_data.check_pool_signer(
+        pool_signer_pda,
+        your_pool_storage_account.key,
+        program_id,
+    )?;
 
-    if user_storage_data.user_wallet != *user_wallet_account.key {
-        msg!("CustomError::UserStorageAuthorityMismatched");
-        return Err(CustomError::UserStorageAuthorityMismatched.into());
+    if your_staking_vault.owner != token_program.key {
+        msg!("CustomError::AccountOwnerShouldBeTokenProgram");
+        return Err(CustomError::AccountOwnerShouldBeTokenProgram.into());
     }
-    if user_storage_data.your_pool != *your_pool_storage_account.key {
-        msg!("CustomError::UserPoolMismatched");
-        return Err(CustomError::UserPoolMismatched.into());
+    let your_staking_vault_data = token::unpack_account(&your_staking_vault.try_borrow_data()?)?;
+    if your_staking_vault_data.owner != pool_signer_address
+        || your_pool_data.your_staking_vault != *your_staking_vault.key
+    {
+        msg!("CustomError::InvalidStakingVault");
+        return Err(CustomError::InvalidStakingVault.into());
     }
+    let your_staking_mint_data =
+        token::unpack_vault_mint(your_staking_mint, token_program, &your_staking_vault_data)?;
+    token::check_user_token_account(
+        user_your_ata,
+        token_program,
+        user_wallet_account.key,
+        your_staking_mint.key,
+    )?;
 
-    let (pool_signer_address, bump_seed) =
-        Pubkey::find_program_address(&[&your_pool_storage_account.key.to_bytes()], program_id);
-
-    let now = Clock::get()?.unix_timestamp as i64;
+    let now = Clock::get()?.unix_timestamp;
+    let amount_withdrawn = user_storage_data.unstake_pending;
     if now > user_storage_data.unstake_pending_date {
+        if your_pool_data.has_receipt_token() && amount_withdrawn > 0 {
+            let (receipt_mint, user_receipt_account) = match (receipt_mint, user_receipt_account) {
+                (Some(mint), Some(account)) => (mint, account),
+                _ => {
+                    msg!("Error: Receipt token pools require the receipt accounts");
+                    return Err(ProgramError::NotEnoughAccountKeys);
+                }
+            };
+            let receipt_decimals =
+                check_receipt_mint(&your_pool_data, receipt_mint, token_program)?;
+
+            msg!("Calling the token program to burn the User receipts...");
+            invoke(
+                &token::burn_checked(
+                    token_program.key,
+                    user_receipt_account.key,
+                    receipt_mint.key,
+                    user_wallet_account.key,
+                    amount_withdrawn,
+                    receipt_decimals,
+                )?,
+                &[
+                    user_receipt_account.clone(),
+                    receipt_mint.clone(),
+                    user_wallet_account.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+        }
         msg!("Calling the token program to transfer YOUR to User from Staking Vault...");
         invoke_signed(
-            &spl_token::instruction::transfer(
+            &token::transfer_checked(
                 token_program.key,
                 your_staking_vault.key,
+                your_staking_mint.key,
                 user_your_ata.key,
                 &pool_signer_address,
-                &[&pool_signer_address],
                 user_storage_data.unstake_pending,
+                your_staking_mint_data.decimals,
             )?,
             &[
                 your_staking_vault.clone(),
+                your_staking_mint.clone(),
                 user_your_ata.clone(),
                 pool_signer_pda.clone(),
                 token_program.clone(),
             ],
-            &[&[&your_pool_storage_account.key.to_bytes(), &[bump_seed]]],
+            &[&[
+                &your_pool_storage_account.key.to_bytes(),
+                &[your_pool_data.pda_nonce],
+            ]],
+        )?;
+        advance_pool_schedule(
+            &mut your_pool_data,
+            your_pool_storage_account.key,
+            reward_schedule_account,
+            program_id,
+            now,
         )?;
+        utils::update_rewards(&mut your_pool_data, Some(&mut user_storage_data), now)?;
         user_storage_data.balance_your_staked = user_storage_data
             .balance_your_staked
             .checked_sub(user_storage_data.unstake_pending)
             .ok_or(CustomError::AmountOverflow)?;
+        your_pool_data.total_your_staked = your_pool_data
+            .total_your_staked
+            .checked_sub(user_storage_data.unstake_pending)
+            .ok_or(CustomError::AmountOverflow)?;
+        your_pool_data.stats.record_unstake(
+            user_storage_data.balance_your_staked,
+            user_storage_data.unstake_pending,
+        );
     } else {
         msg!("CustomError::UserFinalUnstakeTimeout");
         return Err(CustomError::UserFinalUnstakeTimeout.into());
     }
 
     user_storage_data.unstake_pending = 0u64;
-    your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&your_pool_data.try_to_vec().unwrap());
-    user_data_byte_array[0usize..USER_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&user_storage_data.try_to_vec().unwrap());
+    your_pool_data.stats.last_activity_date = now;
+    your_pool_data.save(your_pool_storage_account)?;
+    user_storage_data.save(user_storage_account)?;
+
+    StakingEvent {
+        kind: StakingEventKind::FinalUnstake,
+        pool: *your_pool_storage_account.key,
+        user: *user_wallet_account.key,
+        amount: amount_withdrawn,
+        balance_your_staked: user_storage_data.balance_your_staked,
+        unstake_pending: user_storage_data.unstake_pending,
+        timestamp: now,
+    }
+    .emit()?;
 
     Ok(())
 }
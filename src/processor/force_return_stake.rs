@@ -0,0 +1,281 @@
+use crate::{
+    error::CustomError,
+    event::{StakingEvent, StakingEventKind},
+    processor::create_user::get_user_storage_address_and_bump_seed,
+    processor::redeem_receipt::get_receipt_stake_escrow_address,
+    state::{User, YourPool},
+    token,
+    utils::{self, constants::WIND_DOWN_GRACE_PERIOD},
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+/// Sends the whole stake of a user, pending unstakes included, and their
+/// unclaimed and vesting rewards back to their token accounts and closes
+/// their user storage, returning its rent to them. Only possible once the
+/// grace period after `WindDownPool` has passed. In receipt token pools the
+/// stake goes to the receipt stake escrow instead, whoever holds the receipts
+/// redeems it from there with `RedeemReceipt`.
+///
+/// 0. `[signer]` Pool Owner Wallet Account
+/// 1. `[writable]` YOUR Pool Storage Account
+/// 2. `[writable]` User Storage Account
+/// 3. `[writable]` User Wallet Account
+/// 4. `[writable]` YOUR Staking Vault
+/// 5. `[writable]` User YOUR ATA
+/// 6. `[]` YOUR Staking Mint
+/// 7. `[]` Pool Signer PDA
+/// 8. `[]` Token Program
+/// 9. `[writable]` YOUR Rewards Vault
+/// 10. `[writable]` User Rewards ATA
+/// 11. `[]` YOUR Rewards Mint
+/// 12. `[]` Rewards Token Program
+/// 13. `[writable]` Receipt Stake Escrow (PDA), required by receipt token pools
+pub fn process_force_return_stake(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let pool_owner_wallet_account = next_account_info(account_info_iter)?;
+    let your_pool_storage_account = next_account_info(account_info_iter)?;
+
+    if !pool_owner_wallet_account.is_signer {
+        msg!("ProgramError::MissingRequiredSignature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
+
+    if your_pool_data.owner_wallet != *pool_owner_wallet_account.key {
+        msg!("CustomError::PoolOwnerMismatched");
+        return Err(CustomError::PoolOwnerMismatched.into());
+    }
+    if your_pool_data.wind_down_date == 0 {
+        msg!("CustomError::PoolNotWindingDown");
+        return Err(CustomError::PoolNotWindingDown.into());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let grace_period_end = your_pool_data
+        .wind_down_date
+        .checked_add(WIND_DOWN_GRACE_PERIOD)
+        .ok_or(CustomError::AmountOverflow)?;
+    if now < grace_period_end {
+        msg!("CustomError::WindDownGracePeriod");
+        return Err(CustomError::WindDownGracePeriod.into());
+    }
+
+    let user_storage_account = next_account_info(account_info_iter)?;
+    let user_wallet_account = next_account_info(account_info_iter)?;
+    let your_staking_vault = next_account_info(account_info_iter)?;
+    let user_your_ata = next_account_info(account_info_iter)?;
+    let your_staking_mint = next_account_info(account_info_iter)?;
+    let pool_signer_pda = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let your_rewards_vault = next_account_info(account_info_iter)?;
+    let user_rewards_ata = next_account_info(account_info_iter)?;
+    let your_rewards_mint = next_account_info(account_info_iter)?;
+    let rewards_token_program = next_account_info(account_info_iter)?;
+    let receipt_stake_escrow = next_account_info(account_info_iter).ok();
+    token::check_token_program(token_program)?;
+    token::check_token_program(rewards_token_program)?;
+
+    let (user_storage_address, _bump_seed) = get_user_storage_address_and_bump_seed(
+        user_wallet_account.key,
+        your_pool_storage_account.key,
+        program_id,
+    );
+    if user_storage_address != *user_storage_account.key {
+        msg!("Error: User Storage address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let mut user_storage_data = User::load_for_wallet(
+        user_storage_account,
+        program_id,
+        user_wallet_account.key,
+        your_pool_storage_account.key,
+    )?;
+
+    if your_staking_vault.owner != token_program.key
+        || your_rewards_vault.owner != rewards_token_program.key
+    {
+        msg!("CustomError::AccountOwnerShouldBeTokenProgram");
+        return Err(CustomError::AccountOwnerShouldBeTokenProgram.into());
+    }
+    let your_staking_vault_data = token::unpack_account(&your_staking_vault.try_borrow_data()?)?;
+    let pool_signer_address = your_pool_data.check_pool_signer(
+        pool_signer_pda,
+        your_pool_storage_account.key,
+        program_id,
+    )?;
+    if your_staking_vault_data.owner != pool_signer_address
+        || your_pool_data.your_staking_vault != *your_staking_vault.key
+    {
+        msg!("CustomError::InvalidStakingVault");
+        return Err(CustomError::InvalidStakingVault.into());
+    }
+    let your_staking_mint_data =
+        token::unpack_vault_mint(your_staking_mint, token_program, &your_staking_vault_data)?;
+
+    // The stake can only go back to the user it belongs to, or to the
+    // receipts that stand for it
+    let stake_destination = if your_pool_data.has_receipt_token() {
+        match receipt_stake_escrow {
+            Some(account)
+                if *account.key
+                    == get_receipt_stake_escrow_address(
+                        your_pool_storage_account.key,
+                        program_id,
+                    ) =>
+            {
+                account
+            }
+            _ => {
+                msg!("CustomError::InvalidStakingVault");
+                return Err(CustomError::InvalidStakingVault.into());
+            }
+        }
+    } else {
+        token::check_user_token_account(
+            user_your_ata,
+            token_program,
+            user_wallet_account.key,
+            your_staking_mint.key,
+        )?;
+        user_your_ata
+    };
+
+    let your_rewards_vault_data = token::unpack_account(&your_rewards_vault.try_borrow_data()?)?;
+    if your_rewards_vault_data.owner != pool_signer_address
+        || your_pool_data.your_rewards_vault != *your_rewards_vault.key
+    {
+        msg!("CustomError::InvalidRewardsVault");
+        return Err(CustomError::InvalidRewardsVault.into());
+    }
+    let your_rewards_mint_data = token::unpack_vault_mint(
+        your_rewards_mint,
+        rewards_token_program,
+        &your_rewards_vault_data,
+    )?;
+
+    utils::update_rewards(&mut your_pool_data, Some(&mut user_storage_data), now)?;
+    let amount_returned = user_storage_data.balance_your_staked;
+    let reward_amount = user_storage_data.reward_per_token_pending;
+    // Vesting ends early, there is no pool left to vest in
+    let vesting_amount = user_storage_data
+        .vesting_locked
+        .checked_add(user_storage_data.vesting_unlocked)
+        .ok_or(CustomError::AmountOverflow)?;
+    let rewards_returned = reward_amount
+        .checked_add(vesting_amount)
+        .ok_or(CustomError::AmountOverflow)?;
+    if amount_returned > 0 {
+        msg!("Calling the token program to return YOUR to User from Staking Vault...");
+        invoke_signed(
+            &token::transfer_checked(
+                token_program.key,
+                your_staking_vault.key,
+                your_staking_mint.key,
+                stake_destination.key,
+                &pool_signer_address,
+                amount_returned,
+                your_staking_mint_data.decimals,
+            )?,
+            &[
+                your_staking_vault.clone(),
+                your_staking_mint.clone(),
+                stake_destination.clone(),
+                pool_signer_pda.clone(),
+                token_program.clone(),
+            ],
+            &[&[
+                &your_pool_storage_account.key.to_bytes()[..],
+                &[your_pool_data.pda_nonce],
+            ]],
+        )?;
+    }
+
+    if rewards_returned > 0 {
+        token::check_user_token_account(
+            user_rewards_ata,
+            rewards_token_program,
+            user_wallet_account.key,
+            your_rewards_mint.key,
+        )?;
+
+        msg!("Calling the token program to transfer YOUR to User from Rewards Vault...");
+        invoke_signed(
+            &token::transfer_checked(
+                rewards_token_program.key,
+                your_rewards_vault.key,
+                your_rewards_mint.key,
+                user_rewards_ata.key,
+                &pool_signer_address,
+                rewards_returned,
+                your_rewards_mint_data.decimals,
+            )?,
+            &[
+                your_rewards_vault.clone(),
+                your_rewards_mint.clone(),
+                user_rewards_ata.clone(),
+                pool_signer_pda.clone(),
+                rewards_token_program.clone(),
+            ],
+            &[&[
+                &your_pool_storage_account.key.to_bytes()[..],
+                &[your_pool_data.pda_nonce],
+            ]],
+        )?;
+    }
+
+    your_pool_data.total_your_staked = your_pool_data
+        .total_your_staked
+        .checked_sub(amount_returned)
+        .ok_or(CustomError::AmountOverflow)?;
+    your_pool_data.your_rewards_owed = your_pool_data
+        .your_rewards_owed
+        .checked_sub(reward_amount)
+        .ok_or(CustomError::AmountOverflow)?;
+    your_pool_data.your_rewards_vesting = your_pool_data
+        .your_rewards_vesting
+        .checked_sub(vesting_amount)
+        .ok_or(CustomError::AmountOverflow)?;
+    your_pool_data.user_stake_count = your_pool_data
+        .user_stake_count
+        .checked_sub(1u32)
+        .ok_or(CustomError::AmountOverflow)?;
+    your_pool_data.stats.record_unstake(0u64, amount_returned);
+    // Vesting rewards were counted when they were claimed
+    your_pool_data.stats.rewards_claimed = your_pool_data
+        .stats
+        .rewards_claimed
+        .saturating_add(reward_amount);
+    your_pool_data.stats.last_activity_date = now;
+    your_pool_data.save(your_pool_storage_account)?;
+
+    msg!("Closing the User Data Storage account and transferring lamports to User wallet...");
+    utils::close_account(
+        user_storage_account,
+        user_wallet_account,
+        &mut user_storage_account.try_borrow_mut_data()?,
+    )?;
+
+    StakingEvent {
+        kind: StakingEventKind::ForceReturnStake,
+        pool: *your_pool_storage_account.key,
+        user: *user_wallet_account.key,
+        amount: amount_returned,
+        balance_your_staked: 0u64,
+        unstake_pending: 0u64,
+        timestamp: now,
+    }
+    .emit()?;
+
+    Ok(())
+}
@@ -1,9 +1,12 @@
 use crate::{
     error::CustomError,
+    event::{StakingEvent, StakingEventKind},
+    processor::create_user::create_and_allocate_account_raw,
     state::{AccTypesWithVersion, YourPool, YOUR_POOL_STORAGE_TOTAL_BYTES},
+    token,
 };
 
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
@@ -11,27 +14,38 @@ use solana_program::{
     msg,
     program::invoke,
     program_error::ProgramError,
-    program_pack::Pack,
     pubkey::Pubkey,
-    rent::Rent,
-    sysvar::Sysvar,
+    system_program,
+    sysvar::{self, Sysvar},
 };
-use spl_token::state::Account as TokenAccount;
 
-/// 0. `[signer]` Pool Owner Wallet Account
-/// 1. `[writable]` YOUR Pool Storage Account
+/// Creates the pool storage account at the PDA derived from the staking mint,
+/// the rewards mint and `pool_index`, see `get_pool_storage_address`. Both
+/// vaults are created as fresh token accounts at PDAs of the pool storage and
+/// owned by the pool signer, see `get_staking_vault_address`.
+///
+/// Each mint may live under the legacy token program or Token-2022; the
+/// rewards mint uses the Rewards Token Program.
+///
+/// 0. `[signer, writable]` Pool Owner Wallet Account
+/// 1. `[writable]` YOUR Pool Storage Account (PDA)
 /// 2. `[]` YOUR Staking Mint
-/// 3. `[writable]` YOUR Staking Vault
+/// 3. `[writable]` YOUR Staking Vault (PDA)
 /// 4. `[]` YOUR Rewards Mint
-/// 5. `[writable]` YOUR Rewards Vault
+/// 5. `[writable]` YOUR Rewards Vault (PDA)
 /// 6. `[]` Funder Wallet Account
 /// 7. `[writable]` YOUR ATA to Debit (Reward Token)
 /// 8. `[]` Token Program
+/// 9. `[]` System Program
+/// 10. `[]` Pool Signer PDA, with `pool_nonce` as its canonical bump
+/// 11. `[]` Rent Sysvar
+/// 12. `[]` Rewards Token Program
 pub fn process_initialize_your_pool(
     accounts: &[AccountInfo],
     reward_duration: u64,
     pool_nonce: u8,
     fund_pool: u64,
+    pool_index: u64,
     program_id: &Pubkey,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
@@ -44,8 +58,13 @@ pub fn process_initialize_your_pool(
     let funder_wallet_account = next_account_info(account_info_iter)?;
     let your_rewards_ata_to_debit = next_account_info(account_info_iter)?;
     let token_program = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let pool_signer_pda = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+    let rewards_token_program = next_account_info(account_info_iter)?;
 
     msg!("pool_nonce: {}", pool_nonce);
+    msg!("pool_index: {}", pool_index);
     msg!(
         "Pool Authority: {}",
         pool_owner_wallet_account.key.to_string()
@@ -69,32 +88,58 @@ pub fn process_initialize_your_pool(
     }
 
     msg!("Token program: {}", token_program.key.to_string());
+    msg!(
+        "Rewards token program: {}",
+        rewards_token_program.key.to_string()
+    );
 
-    if token_program.key != &spl_token::id() {
-        msg!("CustomError::InvalidTokenProgram");
-        return Err(CustomError::InvalidTokenProgram.into());
-    }
-
-    let rent = Rent::get()?;
+    token::check_token_program(token_program)?;
+    token::check_token_program(rewards_token_program)?;
 
-    if !rent.is_exempt(your_staking_vault.lamports(), your_staking_vault.data_len()) {
-        msg!("CustomError::NotRentExempt");
-        return Err(CustomError::NotRentExempt.into());
+    if *system_program_info.key != system_program::id() {
+        msg!("CustomError::InvalidSystemProgram");
+        return Err(CustomError::InvalidSystemProgram.into());
     }
 
-    if !rent.is_exempt(your_rewards_vault.lamports(), your_rewards_vault.data_len()) {
-        msg!("CustomError::NotRentExempt");
-        return Err(CustomError::NotRentExempt.into());
+    let (pool_storage_address, pool_storage_bump_seed) = get_pool_storage_address_and_bump_seed(
+        your_staking_mint.key,
+        your_rewards_mint.key,
+        pool_index,
+        program_id,
+    );
+    if pool_storage_address != *your_pool_storage_account.key {
+        msg!("Error: Pool Storage address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
     }
 
-    if !rent.is_exempt(
-        your_pool_storage_account.lamports(),
-        your_pool_storage_account.data_len(),
-    ) {
-        msg!("CustomError::NotRentExempt");
-        return Err(CustomError::NotRentExempt.into());
+    if your_pool_storage_account.try_borrow_data()?.first()
+        == Some(&(AccTypesWithVersion::ClosedPool as u8))
+    {
+        msg!("CustomError::PoolAddressClosed");
+        return Err(CustomError::PoolAddressClosed.into());
+    }
+    if !your_pool_storage_account.data_is_empty() {
+        msg!("CustomError::PoolAddressAlreadyInitialized");
+        return Err(CustomError::PoolAddressAlreadyInitialized.into());
     }
 
+    let pool_index_bytes = pool_index.to_le_bytes();
+    let pool_storage_signer_seeds: &[&[_]] = &[
+        &your_staking_mint.key.to_bytes(),
+        &your_rewards_mint.key.to_bytes(),
+        &pool_index_bytes,
+        &[pool_storage_bump_seed],
+    ];
+
+    create_and_allocate_account_raw(
+        *program_id,
+        your_pool_storage_account,
+        system_program_info,
+        pool_owner_wallet_account,
+        YOUR_POOL_STORAGE_TOTAL_BYTES,
+        pool_storage_signer_seeds,
+    )?;
+
     if your_pool_storage_account.data_len() != YOUR_POOL_STORAGE_TOTAL_BYTES {
         msg!("CustomError::DataSizeNotMatched");
         return Err(CustomError::DataSizeNotMatched.into());
@@ -102,69 +147,56 @@ pub fn process_initialize_your_pool(
 
     let (pool_signer_address, bump_seed) =
         Pubkey::find_program_address(&[&your_pool_storage_account.key.to_bytes()], program_id);
-    msg!(
-        "Calling the token program to transfer Staking vault account ownership to Pool program..."
-    );
-    invoke(
-        &spl_token::instruction::set_authority(
-            token_program.clone().key,
-            your_staking_vault.clone().key,
-            Some(&pool_signer_address),
-            spl_token::instruction::AuthorityType::AccountOwner,
-            pool_owner_wallet_account.key,
-            &[&pool_owner_wallet_account.key],
-        )?,
-        &[
-            your_staking_vault.clone(),
-            pool_owner_wallet_account.clone(),
-            token_program.clone(),
-        ],
+    if pool_signer_address != *pool_signer_pda.key || bump_seed != pool_nonce {
+        msg!("CustomError::DerivedKeyInvalid");
+        return Err(CustomError::DerivedKeyInvalid.into());
+    }
+
+    msg!("Creating the Staking vault owned by Pool program...");
+    create_vault(
+        STAKING_VAULT_SEED,
+        your_staking_vault,
+        your_staking_mint,
+        your_pool_storage_account,
+        pool_owner_wallet_account,
+        pool_signer_pda,
+        rent_sysvar_info,
+        system_program_info,
+        token_program,
+        program_id,
     )?;
 
-    msg!(
-        "Calling the token program to transfer Rewards vault account ownership to Pool program..."
-    );
-    invoke(
-        &spl_token::instruction::set_authority(
-            token_program.clone().key,
-            your_rewards_vault.clone().key,
-            Some(&pool_signer_address),
-            spl_token::instruction::AuthorityType::AccountOwner,
-            pool_owner_wallet_account.key,
-            &[&pool_owner_wallet_account.key],
-        )?,
-        &[
-            your_rewards_vault.clone(),
-            pool_owner_wallet_account.clone(),
-            token_program.clone(),
-        ],
+    msg!("Creating the Rewards vault owned by Pool program...");
+    create_vault(
+        REWARDS_VAULT_SEED,
+        your_rewards_vault,
+        your_rewards_mint,
+        your_pool_storage_account,
+        pool_owner_wallet_account,
+        pool_signer_pda,
+        rent_sysvar_info,
+        system_program_info,
+        rewards_token_program,
+        program_id,
     )?;
 
-    let your_staking_vault_data = TokenAccount::unpack(&your_staking_vault.data.borrow())?;
-    if your_staking_vault_data.mint != *your_staking_mint.key {
-        msg!("CustomError::MintMismatched");
-        return Err(CustomError::MintMismatched.into());
-    }
+    let your_staking_vault_data = token::unpack_account(&your_staking_vault.try_borrow_data()?)?;
+    token::unpack_vault_mint(your_staking_mint, token_program, &your_staking_vault_data)?;
 
-    let your_rewards_vault_data = TokenAccount::unpack(&your_rewards_vault.clone().data.borrow())?;
-    if your_rewards_vault_data.mint != *your_rewards_mint.key {
-        msg!("CustomError::MintMismatched");
-        return Err(CustomError::MintMismatched.into());
-    }
-    let your_pool_storage_account_clone = your_pool_storage_account.clone();
-    let mut your_pool_data_byte_array = your_pool_storage_account_clone
-        .data
-        .try_borrow_mut()
-        .unwrap();
+    let your_rewards_vault_data = token::unpack_account(&your_rewards_vault.try_borrow_data()?)?;
+    let your_rewards_mint_data = token::unpack_vault_mint(
+        your_rewards_mint,
+        rewards_token_program,
+        &your_rewards_vault_data,
+    )?;
     let mut your_pool_data: YourPool =
-        YourPool::try_from_slice(&your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES])
-            .unwrap();
+        YourPool::try_from_slice(&your_pool_storage_account.try_borrow_data()?)?;
 
     if your_pool_data.acc_type != 0 {
         msg!("CustomError::PoolAddressAlreadyInitialized");
         return Err(CustomError::PoolAddressAlreadyInitialized.into());
     }
-    your_pool_data.acc_type = AccTypesWithVersion::YourPoolDataV1 as u8;
+    your_pool_data.acc_type = AccTypesWithVersion::YourPoolDataV2 as u8;
     your_pool_data.owner_wallet = *pool_owner_wallet_account.key;
     your_pool_data.your_staking_vault = *your_staking_vault.key;
     your_pool_data.your_reward_rate = 0u64;
@@ -172,76 +204,37 @@ pub fn process_initialize_your_pool(
     your_pool_data.user_stake_count = 0u32;
     your_pool_data.pda_nonce = bump_seed;
     your_pool_data.reward_duration_end = 0u64;
+    your_pool_data.your_staking_mint = *your_staking_mint.key;
+    your_pool_data.your_rewards_mint = *your_rewards_mint.key;
+    your_pool_data.your_rewards_vault = *your_rewards_vault.key;
+    your_pool_data.migrated_from = Pubkey::default();
+    your_pool_data.pool_index = pool_index;
 
-    //your_pool_data.user_stake_count += 1u32; // TODO: Think on it
-
-    your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&your_pool_data.try_to_vec().unwrap());
+    your_pool_data.save(your_pool_storage_account)?;
 
     msg!("Fund pool started...");
 
-    // Fund pool
-    if token_program.key != &spl_token::id() {
-        msg!("CustomError::InvalidTokenProgram");
-        return Err(CustomError::InvalidTokenProgram.into());
-    }
-
-    if your_staking_vault.owner != token_program.key {
-        msg!("CustomError::AccountOwnerShouldBeTokenProgram");
-        return Err(CustomError::AccountOwnerShouldBeTokenProgram.into());
-    }
-
-    let your_staking_vault_data = TokenAccount::unpack(&your_staking_vault.data.borrow())?;
-    let (pool_signer_address, _bump_seed) =
-        Pubkey::find_program_address(&[&your_pool_storage_account.key.to_bytes()], program_id);
-
-    if your_staking_vault_data.owner != pool_signer_address {
-        msg!("CustomError::InvalidStakingVault");
-        return Err(CustomError::InvalidStakingVault.into());
-    }
-
     let now = Clock::get()?.unix_timestamp as u64;
-    let reward_duration_end = your_pool_data.reward_duration_end;
+    let fund_received = if fund_pool > 0 {
+        msg!("Calling the token program to transfer YOUR rewards to Rewards Vault...");
+        token::deposit(
+            rewards_token_program,
+            your_rewards_ata_to_debit,
+            your_rewards_mint,
+            your_rewards_vault,
+            funder_wallet_account,
+            fund_pool,
+            your_rewards_mint_data.decimals,
+        )?
+    } else {
+        0u64
+    };
     msg!("now: {}", now);
-    msg!("reward_duration_end: {}", reward_duration_end);
     msg!("fund_pool: {}", fund_pool);
-    if now < reward_duration_end {
-        let remaining_duration = reward_duration_end
-            .checked_sub(now)
-            .ok_or(CustomError::AmountOverflow)?;
-        let rewards_left_amount = remaining_duration
-            .checked_mul(your_pool_data.your_reward_rate)
-            .ok_or(CustomError::AmountOverflow)?;
-        your_pool_data.your_reward_rate = fund_pool
-            .checked_add(rewards_left_amount)
-            .ok_or(CustomError::AmountOverflow)?
-            .checked_div(your_pool_data.your_epoch_duration)
-            .ok_or(CustomError::AmountOverflow)?;
-    } else {
-        your_pool_data.your_reward_rate = fund_pool
-            .checked_div(your_pool_data.your_epoch_duration)
-            .ok_or(CustomError::AmountOverflow)?;
-    }
+    your_pool_data.your_reward_rate = fund_received
+        .checked_div(your_pool_data.your_epoch_duration)
+        .ok_or(CustomError::AmountOverflow)?;
 
-    if fund_pool > 0 {
-        msg!("Calling the token program to transfer YOUR rewards to Rewards Vault...");
-        invoke(
-            &spl_token::instruction::transfer(
-                token_program.clone().key,
-                your_rewards_ata_to_debit.key,
-                your_rewards_vault.key,
-                funder_wallet_account.key,
-                &[],
-                fund_pool,
-            )?,
-            &[
-                your_rewards_ata_to_debit.clone(),
-                your_rewards_vault.clone(),
-                funder_wallet_account.clone(),
-                token_program.clone(),
-            ],
-        )?;
-    }
     msg!(
         "your_pool_data.your_reward_rate: {}",
         your_pool_data.your_reward_rate
@@ -249,8 +242,133 @@ pub fn process_initialize_your_pool(
     your_pool_data.reward_duration_end = now
         .checked_add(your_pool_data.your_epoch_duration)
         .ok_or(CustomError::AmountOverflow)?;
-    your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&your_pool_data.try_to_vec().unwrap());
+    your_pool_data.last_update_time = now;
+    your_pool_data.stats.rewards_funded = your_pool_data
+        .stats
+        .rewards_funded
+        .saturating_add(fund_received);
+    your_pool_data.stats.last_activity_date = now as i64;
+    your_pool_data.save(your_pool_storage_account)?;
+
+    StakingEvent {
+        kind: StakingEventKind::FundPool,
+        pool: *your_pool_storage_account.key,
+        user: *funder_wallet_account.key,
+        amount: fund_received,
+        balance_your_staked: 0u64,
+        unstake_pending: 0u64,
+        timestamp: now as i64,
+    }
+    .emit()?;
 
     Ok(())
 }
+
+/// Derives the pool storage account address for the given mints and pool index
+pub fn get_pool_storage_address(
+    staking_mint: &Pubkey,
+    rewards_mint: &Pubkey,
+    pool_index: u64,
+    program_id: &Pubkey,
+) -> Pubkey {
+    get_pool_storage_address_and_bump_seed(staking_mint, rewards_mint, pool_index, program_id).0
+}
+
+pub fn get_pool_storage_address_and_bump_seed(
+    staking_mint: &Pubkey,
+    rewards_mint: &Pubkey,
+    pool_index: u64,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            &staking_mint.to_bytes(),
+            &rewards_mint.to_bytes(),
+            &pool_index.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+pub const STAKING_VAULT_SEED: &[u8] = b"staking_vault";
+pub const REWARDS_VAULT_SEED: &[u8] = b"rewards_vault";
+
+/// Derives the staking vault address of the given pool
+pub fn get_staking_vault_address(pool_storage: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_vault_address_and_bump_seed(STAKING_VAULT_SEED, pool_storage, program_id).0
+}
+
+/// Derives the rewards vault address of the given pool
+pub fn get_rewards_vault_address(pool_storage: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_vault_address_and_bump_seed(REWARDS_VAULT_SEED, pool_storage, program_id).0
+}
+
+pub fn get_vault_address_and_bump_seed(
+    vault_seed: &[u8],
+    pool_storage: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[vault_seed, &pool_storage.to_bytes()], program_id)
+}
+
+/// Creates a token account of `mint` at the PDA of `vault_seed` and the pool
+/// storage, owned by `pool_signer`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_vault<'a>(
+    vault_seed: &[u8],
+    vault: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    pool_storage: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    pool_signer: &AccountInfo<'a>,
+    rent_sysvar_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let (vault_address, vault_bump_seed) =
+        get_vault_address_and_bump_seed(vault_seed, pool_storage.key, program_id);
+    if vault_address != *vault.key {
+        msg!("Error: Vault address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if !vault.data_is_empty() {
+        msg!("CustomError::PoolAddressAlreadyInitialized");
+        return Err(CustomError::PoolAddressAlreadyInitialized.into());
+    }
+
+    if *rent_sysvar_info.key != sysvar::rent::id() {
+        msg!("ProgramError::InvalidArgument");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if mint.owner != token_program.key {
+        msg!("CustomError::MintMismatched");
+        return Err(CustomError::MintMismatched.into());
+    }
+    let vault_len = token::vault_len(&mint.try_borrow_data()?)?;
+
+    let vault_signer_seeds: &[&[_]] =
+        &[vault_seed, &pool_storage.key.to_bytes(), &[vault_bump_seed]];
+
+    create_and_allocate_account_raw(
+        *token_program.key,
+        vault,
+        system_program_info,
+        payer,
+        vault_len,
+        vault_signer_seeds,
+    )?;
+
+    invoke(
+        &token::initialize_account(token_program.key, vault.key, mint.key, pool_signer.key)?,
+        &[
+            vault.clone(),
+            mint.clone(),
+            pool_signer.clone(),
+            rent_sysvar_info.clone(),
+            token_program.clone(),
+        ],
+    )
+}
@@ -0,0 +1,103 @@
+use crate::{
+    error::CustomError,
+    processor::create_user::realloc_account_raw,
+    state::{AccTypesWithVersion, YourPool, YOUR_POOL_STORAGE_TOTAL_BYTES},
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program,
+    sysvar::Sysvar,
+};
+use spl_token::state::Account as TokenAccount;
+
+/// Upgrades a V1 pool to the current layout in place, growing its storage
+/// account. The pool keeps its address, signer PDA and vaults. Anyone can
+/// migrate a pool, the payer only covers the extra rent.
+///
+/// V1 pools did not track their total stake, so the pool starts with none and
+/// every user record adds its balance when it goes through `MigrateUser`.
+///
+/// 0. `[signer, writable]` Payer Account
+/// 1. `[writable]` YOUR Pool Storage Account (V1)
+/// 2. `[]` YOUR Staking Vault
+/// 3. `[]` YOUR Rewards Vault
+/// 4. `[]` System Program
+pub fn process_migrate_pool(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account = next_account_info(account_info_iter)?;
+    let your_pool_storage_account = next_account_info(account_info_iter)?;
+    let your_staking_vault = next_account_info(account_info_iter)?;
+    let your_rewards_vault = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if !payer_account.is_signer {
+        msg!("ProgramError::MissingRequiredSignature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if *system_program_info.key != system_program::id() {
+        msg!("CustomError::InvalidSystemProgram");
+        return Err(CustomError::InvalidSystemProgram.into());
+    }
+
+    let your_pool_data = YourPool::load_any_version(your_pool_storage_account, program_id)?;
+    if your_pool_data.acc_type != AccTypesWithVersion::YourPoolDataV1 as u8 {
+        msg!("CustomError::ExpectedAccountTypeMismatched");
+        return Err(CustomError::ExpectedAccountTypeMismatched.into());
+    }
+
+    let pool_signer_address =
+        your_pool_data.pool_signer_address(your_pool_storage_account.key, program_id)?;
+
+    // V1 pools only ever used the original token program
+    if your_staking_vault.owner != &spl_token::id() || your_rewards_vault.owner != &spl_token::id()
+    {
+        msg!("CustomError::AccountOwnerShouldBeTokenProgram");
+        return Err(CustomError::AccountOwnerShouldBeTokenProgram.into());
+    }
+
+    let your_staking_vault_data = TokenAccount::unpack(&your_staking_vault.try_borrow_data()?)?;
+    if your_staking_vault_data.owner != pool_signer_address
+        || your_pool_data.your_staking_vault != *your_staking_vault.key
+    {
+        msg!("CustomError::InvalidStakingVault");
+        return Err(CustomError::InvalidStakingVault.into());
+    }
+
+    let your_rewards_vault_data = TokenAccount::unpack(&your_rewards_vault.try_borrow_data()?)?;
+    if your_rewards_vault_data.owner != pool_signer_address
+        || *your_rewards_vault.key == *your_staking_vault.key
+    {
+        msg!("CustomError::InvalidRewardsVault");
+        return Err(CustomError::InvalidRewardsVault.into());
+    }
+
+    realloc_account_raw(
+        your_pool_storage_account,
+        system_program_info,
+        payer_account,
+        YOUR_POOL_STORAGE_TOTAL_BYTES,
+    )?;
+
+    let mut migrated_pool_data = your_pool_data;
+    migrated_pool_data.acc_type = AccTypesWithVersion::YourPoolDataV2 as u8;
+    migrated_pool_data.your_staking_mint = your_staking_vault_data.mint;
+    migrated_pool_data.your_rewards_mint = your_rewards_vault_data.mint;
+    migrated_pool_data.your_rewards_vault = *your_rewards_vault.key;
+    migrated_pool_data.migrated_from = *your_pool_storage_account.key;
+    // V1 pools kept no reward checkpoints, accrual starts from the migration
+    let now = Clock::get()?.unix_timestamp;
+    migrated_pool_data.last_update_time = now as u64;
+    migrated_pool_data.stats.last_activity_date = now;
+
+    migrated_pool_data.save(your_pool_storage_account)?;
+
+    Ok(())
+}
@@ -0,0 +1,278 @@
+use crate::{
+    error::CustomError,
+    event::{StakingEvent, StakingEventKind},
+    processor::add_reward_epoch::advance_pool_schedule,
+    processor::create_user::get_user_storage_address_and_bump_seed,
+    processor::stake::check_apr_obligations,
+    state::{User, YourPool},
+    token, utils,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token::state::Account as TokenAccount;
+
+/// Moves the whole staked balance of the user from one pool into another pool
+/// of the same staking mint that accepts stake migrations, straight from vault
+/// to vault and without the unstake cooldown. The user needs a user storage in
+/// the destination pool already. Rewards are settled in the source pool and
+/// stay claimable there, a pending unstake is cancelled.
+///
+/// 0. `[signer]` User Wallet Account
+/// 1. `[writable]` Source User Storage Account
+/// 2. `[writable]` Source YOUR Pool Storage Account
+/// 3. `[writable]` Source YOUR Staking Vault
+/// 4. `[]` Source Pool Signer PDA
+/// 5. `[writable]` Destination User Storage Account
+/// 6. `[writable]` Destination YOUR Pool Storage Account
+/// 7. `[writable]` Destination YOUR Staking Vault
+/// 8. `[]` YOUR Staking Mint
+/// 9. `[]` Token Program
+/// 10. `[writable]` Source Reward Schedule Account (PDA), see `advance_pool_schedule`
+/// 11. `[writable]` Destination Reward Schedule Account (PDA), see `advance_pool_schedule`
+/// 12. `[]` Destination YOUR Rewards Vault, required by fixed-APR pools
+pub fn process_migrate_stake(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_wallet_account = next_account_info(account_info_iter)?;
+    let source_storage_account = next_account_info(account_info_iter)?;
+    let source_pool_storage_account = next_account_info(account_info_iter)?;
+    let source_staking_vault = next_account_info(account_info_iter)?;
+    let source_pool_signer_pda = next_account_info(account_info_iter)?;
+    let destination_storage_account = next_account_info(account_info_iter)?;
+    let destination_pool_storage_account = next_account_info(account_info_iter)?;
+    let destination_staking_vault = next_account_info(account_info_iter)?;
+    let your_staking_mint = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let source_reward_schedule_account = next_account_info(account_info_iter)?;
+    let destination_reward_schedule_account = next_account_info(account_info_iter)?;
+    let destination_rewards_vault = next_account_info(account_info_iter).ok();
+
+    if !user_wallet_account.is_signer {
+        msg!("ProgramError::MissingRequiredSignature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    token::check_token_program(token_program)?;
+
+    if source_pool_storage_account.key == destination_pool_storage_account.key {
+        msg!("ProgramError::InvalidArgument");
+        return Err(ProgramError::InvalidArgument);
+    }
+    for (storage_account, pool_storage_account) in [
+        (source_storage_account, source_pool_storage_account),
+        (
+            destination_storage_account,
+            destination_pool_storage_account,
+        ),
+    ] {
+        let (user_storage_address, _bump_seed) = get_user_storage_address_and_bump_seed(
+            user_wallet_account.key,
+            pool_storage_account.key,
+            program_id,
+        );
+        if user_storage_address != *storage_account.key {
+            msg!("Error: User Storage address does not match seed derivation");
+            return Err(ProgramError::InvalidSeeds);
+        }
+    }
+
+    let mut source_pool_data = YourPool::load(source_pool_storage_account, program_id)?;
+    let mut destination_pool_data = YourPool::load(destination_pool_storage_account, program_id)?;
+    if destination_pool_data.accepts_stake_migrations == 0 {
+        msg!("CustomError::StakeMigrationNotAccepted");
+        return Err(CustomError::StakeMigrationNotAccepted.into());
+    }
+    if destination_pool_data.wind_down_date != 0 {
+        msg!("CustomError::PoolWindingDown");
+        return Err(CustomError::PoolWindingDown.into());
+    }
+    // Receipts would no longer match the stake of either pool
+    if source_pool_data.has_receipt_token() || destination_pool_data.has_receipt_token() {
+        msg!("CustomError::ReceiptPoolUnsupported");
+        return Err(CustomError::ReceiptPoolUnsupported.into());
+    }
+    if source_pool_data.your_staking_mint != destination_pool_data.your_staking_mint {
+        msg!("CustomError::MintMismatched");
+        return Err(CustomError::MintMismatched.into());
+    }
+
+    let mut source_storage_data = User::load_for_wallet(
+        source_storage_account,
+        program_id,
+        user_wallet_account.key,
+        source_pool_storage_account.key,
+    )?;
+    let mut destination_storage_data = User::load_for_wallet(
+        destination_storage_account,
+        program_id,
+        user_wallet_account.key,
+        destination_pool_storage_account.key,
+    )?;
+
+    let amount = source_storage_data.balance_your_staked;
+    if amount == 0u64 {
+        msg!("CustomError::AmountMustBeGreaterThanZero");
+        return Err(CustomError::AmountMustBeGreaterThanZero.into());
+    }
+
+    let source_pool_signer_address = source_pool_data.check_pool_signer(
+        source_pool_signer_pda,
+        source_pool_storage_account.key,
+        program_id,
+    )?;
+    let source_staking_vault_data = check_staking_vault(
+        &source_pool_data,
+        &source_pool_signer_address,
+        source_staking_vault,
+        token_program,
+    )?;
+    let destination_pool_signer_address = destination_pool_data
+        .pool_signer_address(destination_pool_storage_account.key, program_id)?;
+    let destination_staking_vault_data = check_staking_vault(
+        &destination_pool_data,
+        &destination_pool_signer_address,
+        destination_staking_vault,
+        token_program,
+    )?;
+    let your_staking_mint_data =
+        token::unpack_vault_mint(your_staking_mint, token_program, &source_staking_vault_data)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    advance_pool_schedule(
+        &mut source_pool_data,
+        source_pool_storage_account.key,
+        source_reward_schedule_account,
+        program_id,
+        now,
+    )?;
+    utils::update_rewards(&mut source_pool_data, Some(&mut source_storage_data), now)?;
+    source_storage_data.balance_your_staked = 0u64;
+    source_storage_data.unstake_pending = 0u64;
+    source_pool_data.stats.record_unstake(0u64, amount);
+    source_pool_data.stats.last_activity_date = now;
+    source_pool_data.total_your_staked = source_pool_data
+        .total_your_staked
+        .checked_sub(amount)
+        .ok_or(CustomError::AmountOverflow)?;
+
+    let vault_balance_before = destination_staking_vault_data.amount;
+
+    msg!("Calling the token program to transfer to Destination Staking Vault...");
+    invoke_signed(
+        &token::transfer_checked(
+            token_program.key,
+            source_staking_vault.key,
+            your_staking_mint.key,
+            destination_staking_vault.key,
+            &source_pool_signer_address,
+            amount,
+            your_staking_mint_data.decimals,
+        )?,
+        &[
+            source_staking_vault.clone(),
+            your_staking_mint.clone(),
+            destination_staking_vault.clone(),
+            source_pool_signer_pda.clone(),
+            token_program.clone(),
+        ],
+        &[&[
+            &source_pool_storage_account.key.to_bytes(),
+            &[source_pool_data.pda_nonce],
+        ]],
+    )?;
+
+    // Credit what actually arrived, as `Stake` does
+    let vault_balance_after =
+        token::unpack_account(&destination_staking_vault.try_borrow_data()?)?.amount;
+    let amount_received = vault_balance_after
+        .checked_sub(vault_balance_before)
+        .ok_or(CustomError::AmountOverflow)?;
+    if amount_received == 0u64 {
+        msg!("CustomError::AmountMustBeGreaterThanZero");
+        return Err(CustomError::AmountMustBeGreaterThanZero.into());
+    }
+
+    advance_pool_schedule(
+        &mut destination_pool_data,
+        destination_pool_storage_account.key,
+        destination_reward_schedule_account,
+        program_id,
+        now,
+    )?;
+    utils::update_rewards(
+        &mut destination_pool_data,
+        Some(&mut destination_storage_data),
+        now,
+    )?;
+    destination_pool_data.stats.record_stake(
+        destination_storage_data.balance_your_staked,
+        amount_received,
+    );
+    destination_pool_data.stats.last_activity_date = now;
+    destination_storage_data.balance_your_staked = destination_storage_data
+        .balance_your_staked
+        .checked_add(amount_received)
+        .ok_or(CustomError::AmountOverflow)?;
+    destination_pool_data.total_your_staked = destination_pool_data
+        .total_your_staked
+        .checked_add(amount_received)
+        .ok_or(CustomError::AmountOverflow)?;
+    check_apr_obligations(&destination_pool_data, destination_rewards_vault, now)?;
+
+    source_pool_data.save(source_pool_storage_account)?;
+    source_storage_data.save(source_storage_account)?;
+    destination_pool_data.save(destination_pool_storage_account)?;
+    destination_storage_data.save(destination_storage_account)?;
+
+    StakingEvent {
+        kind: StakingEventKind::MigrateStakeOut,
+        pool: *source_pool_storage_account.key,
+        user: *user_wallet_account.key,
+        amount,
+        balance_your_staked: source_storage_data.balance_your_staked,
+        unstake_pending: source_storage_data.unstake_pending,
+        timestamp: now,
+    }
+    .emit()?;
+    StakingEvent {
+        kind: StakingEventKind::MigrateStakeIn,
+        pool: *destination_pool_storage_account.key,
+        user: *user_wallet_account.key,
+        amount: amount_received,
+        balance_your_staked: destination_storage_data.balance_your_staked,
+        unstake_pending: destination_storage_data.unstake_pending,
+        timestamp: now,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+/// Reads the staking vault of `pool`, which has to be the one the pool
+/// stores and be held by its signer PDA.
+fn check_staking_vault(
+    pool: &YourPool,
+    pool_signer_address: &Pubkey,
+    your_staking_vault: &AccountInfo,
+    token_program: &AccountInfo,
+) -> Result<TokenAccount, ProgramError> {
+    if your_staking_vault.owner != token_program.key {
+        msg!("CustomError::AccountOwnerShouldBeTokenProgram");
+        return Err(CustomError::AccountOwnerShouldBeTokenProgram.into());
+    }
+    let your_staking_vault_data = token::unpack_account(&your_staking_vault.try_borrow_data()?)?;
+    if your_staking_vault_data.owner != *pool_signer_address
+        || pool.your_staking_vault != *your_staking_vault.key
+    {
+        msg!("CustomError::InvalidStakingVault");
+        return Err(CustomError::InvalidStakingVault.into());
+    }
+    Ok(your_staking_vault_data)
+}
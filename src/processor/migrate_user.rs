@@ -0,0 +1,104 @@
+use crate::{
+    error::CustomError,
+    processor::add_reward_epoch::advance_pool_schedule,
+    processor::create_user::{get_user_storage_address_and_bump_seed, realloc_account_raw},
+    state::{AccTypesWithVersion, User, YourPool, USER_STORAGE_TOTAL_BYTES},
+    utils,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+    sysvar::Sysvar,
+};
+
+/// Upgrades a V1 user record of a migrated pool to the current layout in
+/// place, keeping the staked balance and timeouts. The balance joins the pool
+/// total and statistics and starts earning rewards from here. Anyone can
+/// migrate a record, the payer only covers the extra rent.
+///
+/// 0. `[signer, writable]` Payer Account
+/// 1. `[writable]` User Storage Account (V1)
+/// 2. `[writable]` YOUR Pool Storage Account
+/// 3. `[]` System Program
+/// 4. `[writable]` Reward Schedule Account (PDA), see `advance_pool_schedule`
+pub fn process_migrate_user(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account = next_account_info(account_info_iter)?;
+    let user_storage_account = next_account_info(account_info_iter)?;
+    let your_pool_storage_account = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let reward_schedule_account = next_account_info(account_info_iter)?;
+
+    if !payer_account.is_signer {
+        msg!("ProgramError::MissingRequiredSignature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if *system_program_info.key != system_program::id() {
+        msg!("CustomError::InvalidSystemProgram");
+        return Err(CustomError::InvalidSystemProgram.into());
+    }
+
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
+
+    let user_storage_data = User::load_any_version(
+        user_storage_account,
+        program_id,
+        your_pool_storage_account.key,
+    )?;
+    if user_storage_data.acc_type != AccTypesWithVersion::UserDataV1 as u8 {
+        msg!("CustomError::ExpectedAccountTypeMismatched");
+        return Err(CustomError::ExpectedAccountTypeMismatched.into());
+    }
+
+    let (user_storage_address, _bump_seed) = get_user_storage_address_and_bump_seed(
+        &user_storage_data.user_wallet,
+        your_pool_storage_account.key,
+        program_id,
+    );
+    if user_storage_address != *user_storage_account.key {
+        msg!("Error: User Storage address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    realloc_account_raw(
+        user_storage_account,
+        system_program_info,
+        payer_account,
+        USER_STORAGE_TOTAL_BYTES,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    advance_pool_schedule(
+        &mut your_pool_data,
+        your_pool_storage_account.key,
+        reward_schedule_account,
+        program_id,
+        now,
+    )?;
+    utils::update_rewards(&mut your_pool_data, None, now)?;
+
+    let mut migrated_user_storage_data = user_storage_data;
+    migrated_user_storage_data.acc_type = AccTypesWithVersion::UserDataV2 as u8;
+    migrated_user_storage_data.reward_per_token_complete = your_pool_data.reward_per_token_stored;
+    your_pool_data.total_your_staked = your_pool_data
+        .total_your_staked
+        .checked_add(migrated_user_storage_data.balance_your_staked)
+        .ok_or(CustomError::AmountOverflow)?;
+    // Counted like a stake, so that leaving the pool later balances out
+    your_pool_data
+        .stats
+        .record_stake(0, migrated_user_storage_data.balance_your_staked);
+    your_pool_data.stats.last_activity_date = now;
+
+    migrated_user_storage_data.save(user_storage_account)?;
+    your_pool_data.save(your_pool_storage_account)?;
+
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use crate::{
+    error::CustomError,
+    event::{StakingEvent, StakingEventKind},
+    processor::enable_receipt_token::get_receipt_mint_address,
+    processor::initialize_pool::get_vault_address_and_bump_seed,
+    token,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+/// Burns `amount` receipts of the holder and pays out the same amount of
+/// stake from the receipt stake escrow, which `ForceReturnStake` fills with
+/// the stake of a receipt token pool that wound down. Works after `ClosePool`
+/// as well, nothing is read from the pool storage. While the pool is live the
+/// owner of a position redeems its receipts with `Unstake` and `FinalUnstake`.
+///
+/// 0. `[signer]` Holder Wallet Account
+/// 1. `[writable]` Holder Receipt Account
+/// 2. `[writable]` Receipt Mint
+/// 3. `[]` YOUR Pool Storage Account, or what `ClosePool` left of it
+/// 4. `[writable]` Receipt Stake Escrow (PDA)
+/// 5. `[writable]` Holder YOUR Account
+/// 6. `[]` YOUR Staking Mint
+/// 7. `[]` Token Program
+/// 8. `[]` Receipt Escrow Authority (PDA)
+pub fn process_redeem_receipt(
+    accounts: &[AccountInfo],
+    amount: u64,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let holder_wallet_account = next_account_info(account_info_iter)?;
+    let holder_receipt_account = next_account_info(account_info_iter)?;
+    let receipt_mint = next_account_info(account_info_iter)?;
+    let your_pool_storage_account = next_account_info(account_info_iter)?;
+    let receipt_stake_escrow = next_account_info(account_info_iter)?;
+    let holder_your_account = next_account_info(account_info_iter)?;
+    let your_staking_mint = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let receipt_escrow_authority = next_account_info(account_info_iter)?;
+
+    if !holder_wallet_account.is_signer {
+        msg!("ProgramError::MissingRequiredSignature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    token::check_token_program(token_program)?;
+    if amount == 0u64 {
+        msg!("CustomError::AmountMustBeGreaterThanZero");
+        return Err(CustomError::AmountMustBeGreaterThanZero.into());
+    }
+
+    if get_receipt_mint_address(your_pool_storage_account.key, program_id) != *receipt_mint.key
+        || receipt_mint.owner != token_program.key
+    {
+        msg!("CustomError::InvalidReceiptMint");
+        return Err(CustomError::InvalidReceiptMint.into());
+    }
+    let receipt_mint_data = token::unpack_mint(&receipt_mint.try_borrow_data()?)?;
+
+    let (escrow_authority, escrow_authority_bump_seed) =
+        get_receipt_escrow_authority_and_bump_seed(your_pool_storage_account.key, program_id);
+    if escrow_authority != *receipt_escrow_authority.key {
+        msg!("CustomError::DerivedKeyInvalid");
+        return Err(CustomError::DerivedKeyInvalid.into());
+    }
+    if get_receipt_stake_escrow_address(your_pool_storage_account.key, program_id)
+        != *receipt_stake_escrow.key
+        || receipt_stake_escrow.owner != token_program.key
+    {
+        msg!("CustomError::InvalidStakingVault");
+        return Err(CustomError::InvalidStakingVault.into());
+    }
+    let receipt_stake_escrow_data =
+        token::unpack_account(&receipt_stake_escrow.try_borrow_data()?)?;
+    let your_staking_mint_data =
+        token::unpack_vault_mint(your_staking_mint, token_program, &receipt_stake_escrow_data)?;
+
+    msg!("Calling the token program to burn the Holder receipts...");
+    invoke(
+        &token::burn_checked(
+            token_program.key,
+            holder_receipt_account.key,
+            receipt_mint.key,
+            holder_wallet_account.key,
+            amount,
+            receipt_mint_data.decimals,
+        )?,
+        &[
+            holder_receipt_account.clone(),
+            receipt_mint.clone(),
+            holder_wallet_account.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    msg!("Calling the token program to transfer YOUR to Holder from Receipt Stake Escrow...");
+    invoke_signed(
+        &token::transfer_checked(
+            token_program.key,
+            receipt_stake_escrow.key,
+            your_staking_mint.key,
+            holder_your_account.key,
+            receipt_escrow_authority.key,
+            amount,
+            your_staking_mint_data.decimals,
+        )?,
+        &[
+            receipt_stake_escrow.clone(),
+            your_staking_mint.clone(),
+            holder_your_account.clone(),
+            receipt_escrow_authority.clone(),
+            token_program.clone(),
+        ],
+        &[&[
+            RECEIPT_ESCROW_AUTHORITY_SEED,
+            &your_pool_storage_account.key.to_bytes(),
+            &[escrow_authority_bump_seed],
+        ]],
+    )?;
+
+    StakingEvent {
+        kind: StakingEventKind::RedeemReceipt,
+        pool: *your_pool_storage_account.key,
+        user: *holder_wallet_account.key,
+        amount,
+        balance_your_staked: 0u64,
+        unstake_pending: 0u64,
+        timestamp: Clock::get()?.unix_timestamp,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+pub const RECEIPT_STAKE_ESCROW_SEED: &[u8] = b"receipt_stake_escrow";
+pub const RECEIPT_ESCROW_AUTHORITY_SEED: &[u8] = b"receipt_escrow";
+
+/// Derives the receipt stake escrow address of the given pool
+pub fn get_receipt_stake_escrow_address(pool_storage: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_vault_address_and_bump_seed(RECEIPT_STAKE_ESCROW_SEED, pool_storage, program_id).0
+}
+
+/// Derives the owner of the receipt stake escrow of the given pool. Unlike the
+/// pool signer it needs nothing from the pool storage, which `ClosePool`
+/// does not keep.
+pub fn get_receipt_escrow_authority_and_bump_seed(
+    pool_storage: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[RECEIPT_ESCROW_AUTHORITY_SEED, &pool_storage.to_bytes()],
+        program_id,
+    )
+}
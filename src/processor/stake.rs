@@ -1,24 +1,41 @@
 use crate::{
     error::CustomError,
+    event::{StakingEvent, StakingEventKind},
+    processor::add_reward_epoch::advance_pool_schedule,
     processor::create_user::get_user_storage_address_and_bump_seed,
-    state::{
-        AccTypesWithVersion, User, YourPool, USER_STORAGE_TOTAL_BYTES,
-        YOUR_POOL_STORAGE_TOTAL_BYTES,
-    },
+    processor::enable_receipt_token::check_receipt_mint,
+    state::{EmissionMode, User, YourPool},
+    token, utils,
 };
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
-    program::invoke,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
-    program_pack::Pack,
     pubkey::Pubkey,
+    sysvar::Sysvar,
 };
-use spl_token::state::Account as TokenAccount;
 
+/// Deposits `amount_to_deposit` of the staking mint into the staking vault
+/// and credits what arrived to the user. Pools with a receipt token mint the
+/// same amount of receipts to the User Receipt Account, `FinalUnstake` burns
+/// them again.
+///
+/// 0. `[signer]` User Wallet Account
+/// 1. `[writable]` User Storage Account
+/// 2. `[writable]` YOUR Pool Storage Account
+/// 3. `[writable]` YOUR Staking Vault
+/// 4. `[writable]` User YOUR ATA
+/// 5. `[]` Token Program
+/// 6. `[]` YOUR Staking Mint
+/// 7. `[writable]` Reward Schedule Account (PDA), see `advance_pool_schedule`
+/// 8. `[]` YOUR Rewards Vault, required by fixed-APR pools
+/// 9. `[writable]` Receipt Mint, required by receipt token pools
+/// 10. `[writable]` User Receipt Account, required by receipt token pools
+/// 11. `[]` Pool Signer PDA, required by receipt token pools
 pub fn process_stake(
     accounts: &[AccountInfo],
     amount_to_deposit: u64,
@@ -31,11 +48,18 @@ pub fn process_stake(
     let your_staking_vault = next_account_info(account_info_iter)?;
     let user_your_ata = next_account_info(account_info_iter)?;
     let token_program = next_account_info(account_info_iter)?;
+    let your_staking_mint = next_account_info(account_info_iter)?;
+    let reward_schedule_account = next_account_info(account_info_iter)?;
+    let your_rewards_vault = next_account_info(account_info_iter).ok();
+    let receipt_mint = next_account_info(account_info_iter).ok();
+    let user_receipt_account = next_account_info(account_info_iter).ok();
+    let pool_signer_pda = next_account_info(account_info_iter).ok();
 
     if !user_wallet_account.is_signer {
         msg!("ProgramError::MissingRequiredSignature");
         return Err(ProgramError::MissingRequiredSignature);
     }
+    token::check_token_program(token_program)?;
 
     if amount_to_deposit == 0u64 {
         msg!("CustomError::AmountMustBeGreaterThanZero");
@@ -52,78 +76,176 @@ pub fn process_stake(
         return Err(ProgramError::InvalidSeeds);
     }
 
-    if your_pool_storage_account.data_len() != YOUR_POOL_STORAGE_TOTAL_BYTES {
-        msg!("CustomError::DataSizeNotMatched");
-        return Err(CustomError::DataSizeNotMatched.into());
-    }
-    let mut your_pool_data_byte_array = your_pool_storage_account.data.try_borrow_mut().unwrap();
-    let your_pool_data: YourPool =
-        YourPool::try_from_slice(&your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES])
-            .unwrap();
-    if your_pool_data.acc_type != AccTypesWithVersion::YourPoolDataV1 as u8 {
-        msg!("CustomError::ExpectedAccountTypeMismatched");
-        return Err(CustomError::ExpectedAccountTypeMismatched.into());
-    }
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
 
-    if user_storage_account.data_len() != USER_STORAGE_TOTAL_BYTES {
-        msg!("CustomError::DataSizeNotMatched");
-        return Err(CustomError::DataSizeNotMatched.into());
-    }
-
-    let mut user_data_byte_array = user_storage_account.data.try_borrow_mut().unwrap();
-    let mut user_storage_data: User =
-        User::try_from_slice(&user_data_byte_array[0usize..USER_STORAGE_TOTAL_BYTES]).unwrap();
-    if user_storage_data.acc_type != AccTypesWithVersion::UserDataV1 as u8 {
-        msg!("CustomError::ExpectedAccountTypeMismatched");
-        return Err(CustomError::ExpectedAccountTypeMismatched.into());
-    }
+    let mut user_storage_data = User::load_for_wallet(
+        user_storage_account,
+        program_id,
+        user_wallet_account.key,
+        your_pool_storage_account.key,
+    )?;
 
-    if user_storage_data.user_wallet != *user_wallet_account.key {
-        msg!("CustomError::UserStorageAuthorityMismatched");
-        return Err(CustomError::UserStorageAuthorityMismatched.into());
-    }
-    if user_storage_data.your_pool != *your_pool_storage_account.key {
-        msg!("CustomError::UserPoolMismatched");
-        return Err(CustomError::UserPoolMismatched.into());
+    if your_pool_data.wind_down_date != 0 {
+        msg!("CustomError::PoolWindingDown");
+        return Err(CustomError::PoolWindingDown.into());
     }
 
     if your_staking_vault.owner != token_program.key {
         msg!("CustomError::AccountOwnerShouldBeTokenProgram");
         return Err(CustomError::AccountOwnerShouldBeTokenProgram.into());
     }
-    let your_staking_vault_data = TokenAccount::unpack(&your_staking_vault.data.borrow())?;
-    let (pool_signer_address, _bump_seed) =
-        Pubkey::find_program_address(&[&your_pool_storage_account.key.to_bytes()], program_id);
-    if your_staking_vault_data.owner != pool_signer_address {
+    let your_staking_vault_data = token::unpack_account(&your_staking_vault.try_borrow_data()?)?;
+    let pool_signer_address =
+        your_pool_data.pool_signer_address(your_pool_storage_account.key, program_id)?;
+    if your_staking_vault_data.owner != pool_signer_address
+        || your_pool_data.your_staking_vault != *your_staking_vault.key
+    {
         msg!("CustomError::InvalidStakingVault");
         return Err(CustomError::InvalidStakingVault.into());
     }
+    if your_pool_data.your_staking_mint != *your_staking_mint.key {
+        msg!("CustomError::MintMismatched");
+        return Err(CustomError::MintMismatched.into());
+    }
+
+    let your_staking_mint_data =
+        token::unpack_vault_mint(your_staking_mint, token_program, &your_staking_vault_data)?;
+    let vault_balance_before = your_staking_vault_data.amount;
 
     msg!("Calling the token program to transfer to Staking Vault...");
     invoke(
-        &spl_token::instruction::transfer(
+        &token::transfer_checked(
             token_program.key,
             user_your_ata.key,
+            your_staking_mint.key,
             your_staking_vault.key,
             user_wallet_account.key,
-            &[],
             amount_to_deposit,
+            your_staking_mint_data.decimals,
         )?,
         &[
             user_your_ata.clone(),
+            your_staking_mint.clone(),
             your_staking_vault.clone(),
             user_wallet_account.clone(),
             token_program.clone(),
         ],
     )?;
+
+    // Credit what actually arrived, transfer fees and hooks can make it less
+    // than `amount_to_deposit`
+    let vault_balance_after = token::unpack_account(&your_staking_vault.try_borrow_data()?)?.amount;
+    let amount_received = vault_balance_after
+        .checked_sub(vault_balance_before)
+        .ok_or(CustomError::AmountOverflow)?;
+    if amount_received == 0u64 {
+        msg!("CustomError::AmountMustBeGreaterThanZero");
+        return Err(CustomError::AmountMustBeGreaterThanZero.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    advance_pool_schedule(
+        &mut your_pool_data,
+        your_pool_storage_account.key,
+        reward_schedule_account,
+        program_id,
+        now,
+    )?;
+    utils::update_rewards(&mut your_pool_data, Some(&mut user_storage_data), now)?;
+    your_pool_data
+        .stats
+        .record_stake(user_storage_data.balance_your_staked, amount_received);
     user_storage_data.balance_your_staked = user_storage_data
         .balance_your_staked
-        .checked_add(amount_to_deposit)
+        .checked_add(amount_received)
         .ok_or(CustomError::AmountOverflow)?;
-    your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&your_pool_data.try_to_vec().unwrap());
-    user_data_byte_array[0usize..USER_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&user_storage_data.try_to_vec().unwrap());
+    your_pool_data.total_your_staked = your_pool_data
+        .total_your_staked
+        .checked_add(amount_received)
+        .ok_or(CustomError::AmountOverflow)?;
+
+    check_apr_obligations(&your_pool_data, your_rewards_vault, now)?;
+
+    if your_pool_data.has_receipt_token() {
+        let (receipt_mint, user_receipt_account, pool_signer_pda) =
+            match (receipt_mint, user_receipt_account, pool_signer_pda) {
+                (Some(mint), Some(account), Some(signer)) => (mint, account, signer),
+                _ => {
+                    msg!("Error: Receipt token pools require the receipt accounts");
+                    return Err(ProgramError::NotEnoughAccountKeys);
+                }
+            };
+        let receipt_decimals = check_receipt_mint(&your_pool_data, receipt_mint, token_program)?;
+        your_pool_data.check_pool_signer(
+            pool_signer_pda,
+            your_pool_storage_account.key,
+            program_id,
+        )?;
+
+        msg!("Calling the token program to mint receipts to User...");
+        invoke_signed(
+            &token::mint_to_checked(
+                token_program.key,
+                receipt_mint.key,
+                user_receipt_account.key,
+                &pool_signer_address,
+                amount_received,
+                receipt_decimals,
+            )?,
+            &[
+                receipt_mint.clone(),
+                user_receipt_account.clone(),
+                pool_signer_pda.clone(),
+                token_program.clone(),
+            ],
+            &[&[
+                &your_pool_storage_account.key.to_bytes(),
+                &[your_pool_data.pda_nonce],
+            ]],
+        )?;
+    }
+
+    your_pool_data.stats.last_activity_date = now;
+    your_pool_data.save(your_pool_storage_account)?;
+    user_storage_data.save(user_storage_account)?;
 
+    StakingEvent {
+        kind: StakingEventKind::Stake,
+        pool: *your_pool_storage_account.key,
+        user: *user_wallet_account.key,
+        amount: amount_received,
+        balance_your_staked: user_storage_data.balance_your_staked,
+        unstake_pending: user_storage_data.unstake_pending,
+        timestamp: now,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+/// A fixed APR is only promised on stake the rewards vault can pay it on, so
+/// fixed-APR pools check the vault after crediting new stake.
+pub fn check_apr_obligations(
+    pool: &YourPool,
+    your_rewards_vault: Option<&AccountInfo>,
+    now: i64,
+) -> ProgramResult {
+    if pool.emission_mode != EmissionMode::FixedApr as u8 {
+        return Ok(());
+    }
+    let your_rewards_vault = match your_rewards_vault {
+        Some(account) if *account.key == pool.your_rewards_vault => account,
+        _ => {
+            msg!("CustomError::InvalidRewardsVault");
+            return Err(CustomError::InvalidRewardsVault.into());
+        }
+    };
+    let rewards_available = token::unpack_account(&your_rewards_vault.try_borrow_data()?)?.amount;
+    let projected_obligations = utils::projected_obligations(pool, now)?;
+    if projected_obligations > rewards_available {
+        msg!("projected_obligations: {}", projected_obligations);
+        msg!("CustomError::InsufficientRewardsForApr");
+        return Err(CustomError::InsufficientRewardsForApr.into());
+    }
     Ok(())
 }
@@ -0,0 +1,44 @@
+use crate::{error::CustomError, event::StakingEventKind, state::EmissionMode, utils};
+
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
+
+/// Starts a reward period of `duration` in which every staked token earns
+/// `apr_bps` a year instead of a share of a fixed budget, funding it with
+/// `amount`, which may be zero when the vault already holds enough. From here
+/// on `Stake` refuses deposits the rewards vault could not pay the APR on
+/// until the end of the period, see `utils::projected_obligations`. Only
+/// possible once the current period and every scheduled epoch are over.
+///
+/// 0. `[signer]` Pool Owner Wallet Account
+/// 1. `[writable]` YOUR Pool Storage Account
+/// 2. `[writable]` YOUR Rewards Vault
+/// 3. `[writable]` YOUR ATA to Debit (Reward Token)
+/// 4. `[]` YOUR Rewards Mint
+/// 5. `[]` Rewards Token Program
+pub fn process_start_fixed_apr(
+    accounts: &[AccountInfo],
+    apr_bps: u64,
+    duration: u64,
+    amount: u64,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    if apr_bps == 0u64 || duration == 0u64 {
+        msg!("CustomError::AmountMustBeGreaterThanZero");
+        return Err(CustomError::AmountMustBeGreaterThanZero.into());
+    }
+
+    // Whoever already staked is promised the APR as well,
+    // `utils::start_emission` checks the vault covers it
+    utils::start_emission(
+        accounts,
+        amount,
+        program_id,
+        StakingEventKind::StartFixedApr,
+        |pool, _amount_received, now| {
+            pool.emission_mode = EmissionMode::FixedApr as u8;
+            pool.apr_bps = apr_bps;
+            pool.emission_start = now;
+            Ok(duration)
+        },
+    )
+}
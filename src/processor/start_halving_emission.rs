@@ -0,0 +1,65 @@
+use crate::{
+    error::CustomError,
+    event::StakingEventKind,
+    state::EmissionMode,
+    utils::{self, constants::MIN_DURATION},
+};
+
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
+
+/// Funds a new reward period whose rate halves every `halving_interval`,
+/// starting now. The initial rate is half of what the vault received per
+/// interval, so the whole curve is covered by the funding; the period ends
+/// once the rate reached zero and `RecoverRewards` returns the rounding
+/// leftovers. Only possible once the current period and every scheduled epoch
+/// are over.
+///
+/// 0. `[signer]` Pool Owner Wallet Account
+/// 1. `[writable]` YOUR Pool Storage Account
+/// 2. `[writable]` YOUR Rewards Vault
+/// 3. `[writable]` YOUR ATA to Debit (Reward Token)
+/// 4. `[]` YOUR Rewards Mint
+/// 5. `[]` Rewards Token Program
+pub fn process_start_halving_emission(
+    accounts: &[AccountInfo],
+    halving_interval: u64,
+    amount: u64,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    if amount == 0u64 {
+        msg!("CustomError::AmountMustBeGreaterThanZero");
+        return Err(CustomError::AmountMustBeGreaterThanZero.into());
+    }
+    if halving_interval < MIN_DURATION {
+        msg!("CustomError::InvalidHalvingInterval");
+        return Err(CustomError::InvalidHalvingInterval.into());
+    }
+
+    utils::start_emission(
+        accounts,
+        amount,
+        program_id,
+        StakingEventKind::StartHalvingEmission,
+        |pool, amount_received, now| {
+            // rate * interval * (1 + 1/2 + 1/4 + ...) stays below the funding
+            let initial_reward_rate = amount_received
+                .checked_div(halving_interval)
+                .ok_or(CustomError::AmountOverflow)?
+                / 2;
+            msg!("initial_reward_rate: {}", initial_reward_rate);
+            if initial_reward_rate == 0u64 {
+                msg!("CustomError::AmountMustBeGreaterThanZero");
+                return Err(CustomError::AmountMustBeGreaterThanZero.into());
+            }
+            // the rate reaches zero after as many halvings as it has bits
+            let emission_duration = halving_interval
+                .checked_mul((u64::BITS - initial_reward_rate.leading_zeros()) as u64)
+                .ok_or(CustomError::AmountOverflow)?;
+            pool.emission_mode = EmissionMode::Halving as u8;
+            pool.initial_reward_rate = initial_reward_rate;
+            pool.emission_start = now;
+            pool.halving_interval = halving_interval;
+            Ok(emission_duration)
+        },
+    )
+}
@@ -0,0 +1,128 @@
+use crate::{
+    error::CustomError,
+    event::{StakingEvent, StakingEventKind},
+    processor::add_reward_epoch::advance_pool_schedule,
+    processor::create_user::get_user_storage_address_and_bump_seed,
+    state::{User, YourPool},
+    utils,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+/// Moves the staked balance and the unclaimed rewards of the user to the
+/// user storage of another wallet in the same pool, which has to exist
+/// already. Both users are settled first, so the rewards earned so far stay
+/// earned. A pending unstake is cancelled, vesting rewards stay with the
+/// current user storage, and the later of the two claim timeouts applies
+/// to the destination. Not available in receipt token pools, where the
+/// receipts themselves are transferable.
+///
+/// 0. `[signer]` User Wallet Account
+/// 1. `[writable]` User Storage Account
+/// 2. `[]` Destination Wallet Account
+/// 3. `[writable]` Destination User Storage Account
+/// 4. `[writable]` YOUR Pool Storage Account
+/// 5. `[writable]` Reward Schedule Account (PDA), see `advance_pool_schedule`
+pub fn process_transfer_position(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_wallet_account = next_account_info(account_info_iter)?;
+    let user_storage_account = next_account_info(account_info_iter)?;
+    let destination_wallet_account = next_account_info(account_info_iter)?;
+    let destination_storage_account = next_account_info(account_info_iter)?;
+    let your_pool_storage_account = next_account_info(account_info_iter)?;
+    let reward_schedule_account = next_account_info(account_info_iter)?;
+
+    if !user_wallet_account.is_signer {
+        msg!("ProgramError::MissingRequiredSignature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    for (wallet_account, storage_account) in [
+        (user_wallet_account, user_storage_account),
+        (destination_wallet_account, destination_storage_account),
+    ] {
+        let (user_storage_address, _bump_seed) = get_user_storage_address_and_bump_seed(
+            wallet_account.key,
+            your_pool_storage_account.key,
+            program_id,
+        );
+        if user_storage_address != *storage_account.key {
+            msg!("Error: User Storage address does not match seed derivation");
+            return Err(ProgramError::InvalidSeeds);
+        }
+    }
+    if user_storage_account.key == destination_storage_account.key {
+        msg!("ProgramError::InvalidArgument");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
+    if your_pool_data.has_receipt_token() {
+        msg!("CustomError::ReceiptPoolUnsupported");
+        return Err(CustomError::ReceiptPoolUnsupported.into());
+    }
+    let mut user_storage_data = User::load_for_wallet(
+        user_storage_account,
+        program_id,
+        user_wallet_account.key,
+        your_pool_storage_account.key,
+    )?;
+    let mut destination_storage_data = User::load_for_wallet(
+        destination_storage_account,
+        program_id,
+        destination_wallet_account.key,
+        your_pool_storage_account.key,
+    )?;
+
+    if user_storage_data.balance_your_staked == 0u64 {
+        msg!("CustomError::AmountMustBeGreaterThanZero");
+        return Err(CustomError::AmountMustBeGreaterThanZero.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    advance_pool_schedule(
+        &mut your_pool_data,
+        your_pool_storage_account.key,
+        reward_schedule_account,
+        program_id,
+        now,
+    )?;
+    let amount = utils::move_position(
+        &mut your_pool_data,
+        &mut user_storage_data,
+        &mut destination_storage_data,
+        now,
+    )?;
+    msg!("Moved position to destination");
+
+    your_pool_data.stats.last_activity_date = now;
+    your_pool_data.save(your_pool_storage_account)?;
+    user_storage_data.save(user_storage_account)?;
+    destination_storage_data.save(destination_storage_account)?;
+
+    for (wallet_account, storage_data) in [
+        (user_wallet_account, &user_storage_data),
+        (destination_wallet_account, &destination_storage_data),
+    ] {
+        StakingEvent {
+            kind: StakingEventKind::TransferPosition,
+            pool: *your_pool_storage_account.key,
+            user: *wallet_account.key,
+            amount,
+            balance_your_staked: storage_data.balance_your_staked,
+            unstake_pending: storage_data.unstake_pending,
+            timestamp: now,
+        }
+        .emit()?;
+    }
+
+    Ok(())
+}
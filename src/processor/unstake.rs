@@ -1,18 +1,15 @@
 use crate::{
     error::CustomError,
+    event::{StakingEvent, StakingEventKind},
     processor::create_user::get_user_storage_address_and_bump_seed,
-    state::{
-        AccTypesWithVersion, User, YourPool, USER_STORAGE_TOTAL_BYTES,
-        YOUR_POOL_STORAGE_TOTAL_BYTES,
-    },
+    state::{User, YourPool},
+    token,
 };
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
-    program::invoke_signed,
     program_error::ProgramError,
     pubkey::Pubkey,
     sysvar::clock::Clock,
@@ -28,19 +25,16 @@ pub fn process_unstake(
     let user_wallet_account = next_account_info(account_info_iter)?;
     let user_storage_account = next_account_info(account_info_iter)?;
     let your_pool_storage_account = next_account_info(account_info_iter)?;
-    let _your_staking_vault = next_account_info(account_info_iter)?;
-    let _user_your_ata = next_account_info(account_info_iter)?;
-    let _pool_signer_pda = next_account_info(account_info_iter)?;
+    let your_staking_vault = next_account_info(account_info_iter)?;
+    let user_your_ata = next_account_info(account_info_iter)?;
+    let pool_signer_pda = next_account_info(account_info_iter)?;
     let token_program = next_account_info(account_info_iter)?;
 
     if !user_wallet_account.is_signer {
         msg!("ProgramError::MissingRequiredSignature");
         return Err(ProgramError::MissingRequiredSignature);
     }
-    if token_program.key != &spl_token::id() {
-        msg!("CustomError::InvalidTokenProgram");
-        return Err(CustomError::InvalidTokenProgram.into());
-    }
+    token::check_token_program(token_program)?;
 
     if amount_to_withdraw == 0u64 {
         msg!("CustomError::AmountMustBeGreaterThanZero");
@@ -57,56 +51,63 @@ pub fn process_unstake(
         return Err(ProgramError::InvalidSeeds);
     }
 
-    if your_pool_storage_account.data_len() != YOUR_POOL_STORAGE_TOTAL_BYTES {
-        msg!("CustomError::DataSizeNotMatched");
-        return Err(CustomError::DataSizeNotMatched.into());
-    }
-    let mut your_pool_data_byte_array = your_pool_storage_account.data.try_borrow_mut().unwrap();
-    let your_pool_data: YourPool =
-        YourPool::try_from_slice(&your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES])
-            .unwrap();
-    if your_pool_data.acc_type != AccTypesWithVersion::YourPoolDataV1 as u8 {
-        msg!("CustomError::ExpectedAccountTypeMismatched");
-        return Err(CustomError::ExpectedAccountTypeMismatched.into());
-    }
-
-    if user_storage_account.data_len() != USER_STORAGE_TOTAL_BYTES {
-        msg!("CustomError::DataSizeNotMatched");
-        return Err(CustomError::DataSizeNotMatched.into());
-    }
-
-    let mut user_data_byte_array = user_storage_account.data.try_borrow_mut().unwrap();
-    let mut user_storage_data: User =
-        User::try_from_slice(&user_data_byte_array[0usize..USER_STORAGE_TOTAL_BYTES]).unwrap();
-    if user_storage_data.acc_type != AccTypesWithVersion::UserDataV1 as u8 {
-        msg!("CustomError::ExpectedAccountTypeMismatched");
-        return Err(CustomError::ExpectedAccountTypeMismatched.into());
-    }
+    let your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
+    let mut user_storage_data = User::load_for_wallet(
+        user_storage_account,
+        program_id,
+        user_wallet_account.key,
+        your_pool_storage_account.key,
+    )?;
 
-    if user_storage_data.user_wallet != *user_wallet_account.key {
-        msg!("CustomError::UserStorageAuthorityMismatched");
-        return Err(CustomError::UserStorageAuthorityMismatched.into());
+    // FinalUnstake pays the pending amount out of this vault into this ATA
+    let pool_signer_address = your_pool_data.check_pool_signer(
+        pool_signer_pda,
+        your_pool_storage_account.key,
+        program_id,
+    )?;
+    if your_staking_vault.owner != token_program.key {
+        msg!("CustomError::AccountOwnerShouldBeTokenProgram");
+        return Err(CustomError::AccountOwnerShouldBeTokenProgram.into());
     }
-    if user_storage_data.your_pool != *your_pool_storage_account.key {
-        msg!("CustomError::UserPoolMismatched");
-        return Err(CustomError::UserPoolMismatched.into());
+    let your_staking_vault_data = token::unpack_account(&your_staking_vault.try_borrow_data()?)?;
+    if your_staking_vault_data.owner != pool_signer_address
+        || your_pool_data.your_staking_vault != *your_staking_vault.key
+    {
+        msg!("CustomError::InvalidStakingVault");
+        return Err(CustomError::InvalidStakingVault.into());
     }
+    token::check_user_token_account(
+        user_your_ata,
+        token_program,
+        user_wallet_account.key,
+        &your_staking_vault_data.mint,
+    )?;
 
     if user_storage_data.balance_your_staked < amount_to_withdraw {
         msg!("CustomError::InsufficientFundsToUnstake");
         return Err(CustomError::InsufficientFundsToUnstake.into());
     }
 
-    let now = Clock::get()?.unix_timestamp as i64;
+    let now = Clock::get()?.unix_timestamp;
 
     user_storage_data.unstake_pending = amount_to_withdraw;
-    user_storage_data.unstake_pending_date = now + 2; // pending for 2 seconds
+    user_storage_data.unstake_pending_date = now
+        .checked_add(2) // pending for 2 seconds
+        .ok_or(CustomError::AmountOverflow)?;
     msg!("Moved amount to pending");
 
-    your_pool_data_byte_array[0usize..YOUR_POOL_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&your_pool_data.try_to_vec().unwrap());
-    user_data_byte_array[0usize..USER_STORAGE_TOTAL_BYTES]
-        .copy_from_slice(&user_storage_data.try_to_vec().unwrap());
+    user_storage_data.save(user_storage_account)?;
+
+    StakingEvent {
+        kind: StakingEventKind::Unstake,
+        pool: *your_pool_storage_account.key,
+        user: *user_wallet_account.key,
+        amount: amount_to_withdraw,
+        balance_your_staked: user_storage_data.balance_your_staked,
+        unstake_pending: user_storage_data.unstake_pending,
+        timestamp: now,
+    }
+    .emit()?;
 
     Ok(())
 }
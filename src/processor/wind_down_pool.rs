@@ -0,0 +1,165 @@
+use crate::{
+    error::CustomError,
+    event::{StakingEvent, StakingEventKind},
+    processor::add_reward_epoch::advance_pool_schedule,
+    state::YourPool,
+    token, utils,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use std::convert::TryInto;
+
+/// Ends the reward period now and refunds the rewards that would have been
+/// emitted for the rest of it and for every scheduled epoch, never touching
+/// what is owed, vesting or streaming to stakers. New stakes are refused from here
+/// on and, once `WIND_DOWN_GRACE_PERIOD` has passed, the owner can hand back
+/// the stake of users who never left with `ForceReturnStake` until the pool
+/// can be closed.
+///
+/// 0. `[signer]` Pool Owner Wallet Account
+/// 1. `[writable]` YOUR Pool Storage Account
+/// 2. `[writable]` YOUR Rewards Vault
+/// 3. `[writable]` YOUR Rewards Refund ATA
+/// 4. `[]` YOUR Rewards Mint
+/// 5. `[]` Pool Signer PDA
+/// 6. `[]` Rewards Token Program
+/// 7. `[writable]` Reward Schedule Account (PDA), see `advance_pool_schedule`
+pub fn process_wind_down_pool(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let pool_owner_wallet_account = next_account_info(account_info_iter)?;
+    let your_pool_storage_account = next_account_info(account_info_iter)?;
+    let your_rewards_vault = next_account_info(account_info_iter)?;
+    let your_rewards_refund_ata = next_account_info(account_info_iter)?;
+    let your_rewards_mint = next_account_info(account_info_iter)?;
+    let pool_signer_pda = next_account_info(account_info_iter)?;
+    let rewards_token_program = next_account_info(account_info_iter)?;
+    let reward_schedule_account = next_account_info(account_info_iter)?;
+
+    if !pool_owner_wallet_account.is_signer {
+        msg!("ProgramError::MissingRequiredSignature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    token::check_token_program(rewards_token_program)?;
+
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
+
+    if your_pool_data.owner_wallet != *pool_owner_wallet_account.key {
+        msg!("CustomError::PoolOwnerMismatched");
+        return Err(CustomError::PoolOwnerMismatched.into());
+    }
+    if your_pool_data.wind_down_date != 0 {
+        msg!("CustomError::PoolWindingDown");
+        return Err(CustomError::PoolWindingDown.into());
+    }
+
+    if your_rewards_vault.owner != rewards_token_program.key {
+        msg!("CustomError::AccountOwnerShouldBeTokenProgram");
+        return Err(CustomError::AccountOwnerShouldBeTokenProgram.into());
+    }
+    let your_rewards_vault_data = token::unpack_account(&your_rewards_vault.try_borrow_data()?)?;
+    let pool_signer_address = your_pool_data.check_pool_signer(
+        pool_signer_pda,
+        your_pool_storage_account.key,
+        program_id,
+    )?;
+    if your_rewards_vault_data.owner != pool_signer_address
+        || your_pool_data.your_rewards_vault != *your_rewards_vault.key
+    {
+        msg!("CustomError::InvalidRewardsVault");
+        return Err(CustomError::InvalidRewardsVault.into());
+    }
+    let your_rewards_mint_data = token::unpack_vault_mint(
+        your_rewards_mint,
+        rewards_token_program,
+        &your_rewards_vault_data,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    advance_pool_schedule(
+        &mut your_pool_data,
+        your_pool_storage_account.key,
+        reward_schedule_account,
+        program_id,
+        now,
+    )?;
+    utils::update_rewards(&mut your_pool_data, None, now)?;
+    let remaining_rewards: u64 = if (now as u64) < your_pool_data.reward_duration_end {
+        utils::EmissionCurve::of(&your_pool_data)
+            .emitted(now as u64, your_pool_data.reward_duration_end)?
+            .try_into()
+            .map_err(|_| CustomError::AmountOverflow)?
+    } else {
+        0u64
+    };
+    // What stakers earned, are vesting or are being streamed stays in the
+    // vault, also when the vault holds less than the pool promised
+    let rewards_obligations = your_pool_data
+        .your_rewards_owed
+        .checked_add(your_pool_data.your_rewards_vesting)
+        .and_then(|committed| committed.checked_add(your_pool_data.penalty_stream_remaining))
+        .ok_or(CustomError::AmountOverflow)?;
+    let refundable_rewards = your_rewards_vault_data
+        .amount
+        .saturating_sub(rewards_obligations);
+    // Scheduled epochs never start once the pool winds down
+    let unallocated_rewards = remaining_rewards
+        .checked_add(your_pool_data.your_rewards_scheduled)
+        .ok_or(CustomError::AmountOverflow)?
+        .min(refundable_rewards);
+    msg!("unallocated_rewards: {}", unallocated_rewards);
+
+    your_pool_data.reward_duration_end = your_pool_data.reward_duration_end.min(now as u64);
+    your_pool_data.wind_down_date = now;
+    your_pool_data.next_epoch_start = 0u64;
+    your_pool_data.your_rewards_scheduled = 0u64;
+    your_pool_data.stats.last_activity_date = now;
+    your_pool_data.save(your_pool_storage_account)?;
+
+    if unallocated_rewards > 0 {
+        msg!("Calling the token program to transfer YOUR to Rewards Refund from Rewards Vault...");
+        invoke_signed(
+            &token::transfer_checked(
+                rewards_token_program.key,
+                your_rewards_vault.key,
+                your_rewards_mint.key,
+                your_rewards_refund_ata.key,
+                &pool_signer_address,
+                unallocated_rewards,
+                your_rewards_mint_data.decimals,
+            )?,
+            &[
+                your_rewards_vault.clone(),
+                your_rewards_mint.clone(),
+                your_rewards_refund_ata.clone(),
+                pool_signer_pda.clone(),
+                rewards_token_program.clone(),
+            ],
+            &[&[
+                &your_pool_storage_account.key.to_bytes()[..],
+                &[your_pool_data.pda_nonce],
+            ]],
+        )?;
+    }
+
+    StakingEvent {
+        kind: StakingEventKind::WindDownPool,
+        pool: *your_pool_storage_account.key,
+        user: *pool_owner_wallet_account.key,
+        amount: unallocated_rewards,
+        balance_your_staked: 0u64,
+        unstake_pending: 0u64,
+        timestamp: now,
+    }
+    .emit()?;
+
+    Ok(())
+}
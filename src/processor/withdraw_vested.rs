@@ -0,0 +1,188 @@
+use crate::{
+    error::CustomError,
+    event::{StakingEvent, StakingEventKind},
+    processor::add_reward_epoch::advance_pool_schedule,
+    processor::create_user::get_user_storage_address_and_bump_seed,
+    state::{User, YourPool},
+    token,
+    utils::{self, constants::BPS_DENOMINATOR},
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+/// Pays out the claimed rewards of the user that finished vesting. With
+/// `instant` the still locked part is paid out as well, less
+/// `vesting_penalty_bps` of it, which is streamed to the stakers of the
+/// pool, see `utils::redistribute_rewards`.
+///
+/// 0. `[signer]` User Wallet Account
+/// 1. `[writable]` User Storage Account
+/// 2. `[writable]` YOUR Pool Storage Account
+/// 3. `[writable]` YOUR Rewards Vault
+/// 4. `[writable]` User Rewards ATA
+/// 5. `[]` YOUR Rewards Mint
+/// 6. `[]` Pool Signer PDA
+/// 7. `[]` Rewards Token Program
+/// 8. `[writable]` Reward Schedule Account (PDA), see `advance_pool_schedule`
+pub fn process_withdraw_vested(
+    accounts: &[AccountInfo],
+    instant: bool,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_wallet_account = next_account_info(account_info_iter)?;
+    let user_storage_account = next_account_info(account_info_iter)?;
+    let your_pool_storage_account = next_account_info(account_info_iter)?;
+    let your_rewards_vault = next_account_info(account_info_iter)?;
+    let user_rewards_ata = next_account_info(account_info_iter)?;
+    let your_rewards_mint = next_account_info(account_info_iter)?;
+    let pool_signer_pda = next_account_info(account_info_iter)?;
+    let rewards_token_program = next_account_info(account_info_iter)?;
+    let reward_schedule_account = next_account_info(account_info_iter)?;
+
+    if !user_wallet_account.is_signer {
+        msg!("ProgramError::MissingRequiredSignature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    token::check_token_program(rewards_token_program)?;
+
+    let (user_storage_address, _bump_seed) = get_user_storage_address_and_bump_seed(
+        user_wallet_account.key,
+        your_pool_storage_account.key,
+        program_id,
+    );
+    if user_storage_address != *user_storage_account.key {
+        msg!("Error: User Storage address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
+
+    let mut user_storage_data = User::load_for_wallet(
+        user_storage_account,
+        program_id,
+        user_wallet_account.key,
+        your_pool_storage_account.key,
+    )?;
+
+    if your_rewards_vault.owner != rewards_token_program.key {
+        msg!("CustomError::AccountOwnerShouldBeTokenProgram");
+        return Err(CustomError::AccountOwnerShouldBeTokenProgram.into());
+    }
+    let your_rewards_vault_data = token::unpack_account(&your_rewards_vault.try_borrow_data()?)?;
+    let pool_signer_address = your_pool_data.check_pool_signer(
+        pool_signer_pda,
+        your_pool_storage_account.key,
+        program_id,
+    )?;
+    if your_rewards_vault_data.owner != pool_signer_address
+        || your_pool_data.your_rewards_vault != *your_rewards_vault.key
+    {
+        msg!("CustomError::InvalidRewardsVault");
+        return Err(CustomError::InvalidRewardsVault.into());
+    }
+    let your_rewards_mint_data = token::unpack_vault_mint(
+        your_rewards_mint,
+        rewards_token_program,
+        &your_rewards_vault_data,
+    )?;
+    token::check_user_token_account(
+        user_rewards_ata,
+        rewards_token_program,
+        user_wallet_account.key,
+        your_rewards_mint.key,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    advance_pool_schedule(
+        &mut your_pool_data,
+        your_pool_storage_account.key,
+        reward_schedule_account,
+        program_id,
+        now,
+    )?;
+    utils::update_rewards(&mut your_pool_data, Some(&mut user_storage_data), now)?;
+    utils::settle_vesting(&mut user_storage_data, now)?;
+
+    let mut amount_withdrawn = user_storage_data.vesting_unlocked;
+    let mut penalty = 0u64;
+    if instant {
+        let locked = user_storage_data.vesting_locked;
+        penalty = ((locked as u128) * (your_pool_data.vesting_penalty_bps as u128)
+            / (BPS_DENOMINATOR as u128)) as u64;
+        amount_withdrawn = amount_withdrawn
+            .checked_add(
+                locked
+                    .checked_sub(penalty)
+                    .ok_or(CustomError::AmountOverflow)?,
+            )
+            .ok_or(CustomError::AmountOverflow)?;
+        user_storage_data.vesting_locked = 0u64;
+    }
+    msg!("amount_withdrawn: {}", amount_withdrawn);
+    msg!("penalty: {}", penalty);
+    if amount_withdrawn == 0u64 {
+        msg!("CustomError::AmountMustBeGreaterThanZero");
+        return Err(CustomError::AmountMustBeGreaterThanZero.into());
+    }
+    user_storage_data.vesting_unlocked = 0u64;
+    your_pool_data.your_rewards_vesting = your_pool_data
+        .your_rewards_vesting
+        .checked_sub(amount_withdrawn)
+        .and_then(|vesting| vesting.checked_sub(penalty))
+        .ok_or(CustomError::AmountOverflow)?;
+    utils::redistribute_rewards(&mut your_pool_data, penalty, now)?;
+    // The stakers it goes to claim it once more
+    your_pool_data.stats.rewards_claimed =
+        your_pool_data.stats.rewards_claimed.saturating_sub(penalty);
+
+    msg!("Calling the token program to transfer YOUR to User from Rewards Vault...");
+    invoke_signed(
+        &token::transfer_checked(
+            rewards_token_program.key,
+            your_rewards_vault.key,
+            your_rewards_mint.key,
+            user_rewards_ata.key,
+            &pool_signer_address,
+            amount_withdrawn,
+            your_rewards_mint_data.decimals,
+        )?,
+        &[
+            your_rewards_vault.clone(),
+            your_rewards_mint.clone(),
+            user_rewards_ata.clone(),
+            pool_signer_pda.clone(),
+            rewards_token_program.clone(),
+        ],
+        &[&[
+            &your_pool_storage_account.key.to_bytes(),
+            &[your_pool_data.pda_nonce],
+        ]],
+    )?;
+
+    your_pool_data.stats.last_activity_date = now;
+    your_pool_data.save(your_pool_storage_account)?;
+    user_storage_data.save(user_storage_account)?;
+
+    StakingEvent {
+        kind: StakingEventKind::WithdrawVested,
+        pool: *your_pool_storage_account.key,
+        user: *user_wallet_account.key,
+        amount: amount_withdrawn,
+        balance_your_staked: user_storage_data.balance_your_staked,
+        unstake_pending: user_storage_data.unstake_pending,
+        timestamp: now,
+    }
+    .emit()?;
+
+    Ok(())
+}
@@ -1,12 +1,101 @@
+use crate::error::CustomError;
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::Pubkey;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
 
 pub enum AccTypesWithVersion {
     YourPoolDataV1 = 2,
     UserDataV1 = 3,
+    YourPoolDataV2 = 4,
+    UserDataV2 = 5,
+    RewardScheduleV1 = 6,
+    /// Left at the address of a closed pool, see `utils::close_pool_account`
+    ClosedPool = 7,
 }
 
-pub const YOUR_POOL_STORAGE_TOTAL_BYTES: usize = 94; // Should be 2 bytes less than real size of
+/// How a pool derives its reward rate, see `utils::EmissionCurve`.
+pub enum EmissionMode {
+    /// `your_reward_rate` stays fixed for the whole reward period
+    Flat = 0,
+    /// `initial_reward_rate` halves every `halving_interval` from `emission_start`
+    Halving = 1,
+    /// Every staked token earns `apr_bps` a year, whatever the total stake
+    FixedApr = 2,
+}
+
+/// Cumulative pool statistics for dashboards, kept in the pool storage so
+/// they come with the pool. Migrated pools count the stake of every user
+/// record as it goes through `MigrateUser`.
+/// Never checked, the counters saturate instead of failing an instruction.
+#[derive(Clone, BorshDeserialize, BorshSerialize, Copy, Debug, Default, PartialEq)]
+pub struct PoolStats {
+    /// Rewards that arrived in the rewards vault, after transfer fees
+    pub rewards_funded: u64,
+    /// Rewards claimed by users, less forfeited vesting penalties
+    pub rewards_claimed: u64,
+    /// Stake that entered the pool, after transfer fees
+    pub total_staked: u64,
+    /// Stake that left the pool, by any instruction
+    pub total_unstaked: u64,
+    /// Users with a nonzero `balance_your_staked`
+    pub active_stakers: u32,
+    /// Unix time of the last stake, reward or user storage change
+    pub last_activity_date: i64,
+}
+
+/// Offset of `YourPool::stats` in the pool storage, for clients that only
+/// read the statistics.
+pub const POOL_STATS_OFFSET: usize = 384;
+
+impl PoolStats {
+    /// Counts `amount` staked by a user who held `balance_before`.
+    pub fn record_stake(&mut self, balance_before: u64, amount: u64) {
+        self.total_staked = self.total_staked.saturating_add(amount);
+        if balance_before == 0 && amount != 0 {
+            self.active_stakers = self.active_stakers.saturating_add(1);
+        }
+    }
+
+    /// Counts `amount` leaving the stake of a user who holds `balance_after`.
+    pub fn record_unstake(&mut self, balance_after: u64, amount: u64) {
+        self.total_unstaked = self.total_unstaked.saturating_add(amount);
+        if balance_after == 0 && amount != 0 {
+            self.active_stakers = self.active_stakers.saturating_sub(1);
+        }
+    }
+}
+
+pub const YOUR_POOL_V1_STORAGE_TOTAL_BYTES: usize = 94; // Should be 2 bytes less than real size of
+#[derive(Clone, BorshDeserialize, BorshSerialize, Copy)]
+pub struct YourPoolV1 {
+    pub acc_type: u8,
+    pub owner_wallet: Pubkey,
+    pub your_staking_vault: Pubkey,
+    pub your_reward_rate: u64,
+    pub your_epoch_duration: u64,
+    pub user_stake_count: u32,
+    pub pda_nonce: u8,
+    pub reward_duration_end: u64,
+}
+
+pub const USER_V1_STORAGE_TOTAL_BYTES: usize = 98;
+#[derive(Clone, BorshDeserialize, BorshSerialize, Copy)]
+pub struct UserV1 {
+    pub acc_type: u8,
+    pub user_wallet: Pubkey,
+    pub your_pool: Pubkey,
+    pub balance_your_staked: u64,
+    pub unstake_pending: u64,
+    pub unstake_pending_date: i64,
+    pub nonce: u8,
+    pub claim_timeout_date: i64,
+}
+
+/// Current pool layout. New fields are carved out of `reserved` so the account
+/// size stays fixed and V2 pools never need to be reallocated again.
+pub const YOUR_POOL_STORAGE_TOTAL_BYTES: usize = 512;
 #[derive(Clone, BorshDeserialize, BorshSerialize, Copy)]
 pub struct YourPool {
     pub acc_type: u8,
@@ -17,9 +106,61 @@ pub struct YourPool {
     pub user_stake_count: u32,
     pub pda_nonce: u8,
     pub reward_duration_end: u64,
+    pub your_staking_mint: Pubkey,
+    pub your_rewards_mint: Pubkey,
+    pub your_rewards_vault: Pubkey,
+    /// Set to the pool's own address by `MigratePool`, default for pools
+    /// created as V2
+    pub migrated_from: Pubkey,
+    /// Index the pool storage PDA was derived with, 0 for migrated pools
+    pub pool_index: u64,
+    /// Unix time `WindDownPool` was called, 0 while the pool is running
+    pub wind_down_date: i64,
+    /// Sum of `balance_your_staked` over the users of the pool
+    pub total_your_staked: u64,
+    /// Reward checkpoint, see `utils::update_rewards`
+    pub reward_per_token_stored: u128,
+    pub last_update_time: u64,
+    /// Rewards emitted to stakers and not claimed yet
+    pub your_rewards_owed: u64,
+    /// Start of the next epoch in the reward schedule, 0 if none is left
+    pub next_epoch_start: u64,
+    /// Rewards funded for scheduled epochs that have not started yet
+    pub your_rewards_scheduled: u64,
+    /// `EmissionMode` of the current reward period
+    pub emission_mode: u8,
+    /// Halving mode only: rate at `emission_start`, halved every `halving_interval`
+    pub initial_reward_rate: u64,
+    pub halving_interval: u64,
+    pub emission_start: u64,
+    /// Fixed-APR mode only: yearly reward per staked token, in basis points
+    pub apr_bps: u64,
+    /// Claimed rewards unlock linearly over this period, 0 pays them out at once
+    pub vesting_period: u64,
+    /// Share of the locked rewards forfeited to the stakers by an instant
+    /// `WithdrawVested`, in basis points
+    pub vesting_penalty_bps: u64,
+    /// Claimed rewards held in the vault for vesting users
+    pub your_rewards_vesting: u64,
+    /// Mint of the receipt token `Stake` pays out, default while the pool
+    /// has none, see `EnableReceiptToken`
+    pub receipt_mint: Pubkey,
+    /// Nonzero once the owner lets `MigrateStake` move stake in from other
+    /// pools of the same staking mint
+    pub accepts_stake_migrations: u8,
+    pub stats: PoolStats,
+    /// Forfeited vesting penalties not streamed to the stakers yet, see
+    /// `utils::redistribute_rewards`
+    pub penalty_stream_remaining: u64,
+    /// Time the penalty stream was last settled
+    pub penalty_stream_start: u64,
+    /// Time the penalty stream has been paid out in full
+    pub penalty_stream_end: u64,
+    pub reserved: [u8; 60],
 }
 
-pub const USER_STORAGE_TOTAL_BYTES: usize = 98;
+/// Current user layout, padded like `YourPool`.
+pub const USER_STORAGE_TOTAL_BYTES: usize = 256;
 #[derive(Clone, BorshDeserialize, BorshSerialize, Copy)]
 pub struct User {
     pub acc_type: u8,
@@ -30,4 +171,375 @@ pub struct User {
     pub unstake_pending_date: i64,
     pub nonce: u8,
     pub claim_timeout_date: i64,
+    /// Pool `reward_per_token_stored` the user was last settled at
+    pub reward_per_token_complete: u128,
+    /// Rewards settled to the user and not claimed yet
+    pub reward_per_token_pending: u64,
+    /// Claimed rewards still vesting, unlocking linearly until `vesting_end`
+    pub vesting_locked: u64,
+    pub vesting_start: i64,
+    pub vesting_end: i64,
+    /// Claimed rewards that finished vesting and can be withdrawn
+    pub vesting_unlocked: u64,
+    pub reserved: [u8; 102],
+}
+
+pub const MAX_REWARD_EPOCHS: usize = 16;
+
+#[derive(Clone, BorshDeserialize, BorshSerialize, Copy, Debug, Default, PartialEq)]
+pub struct RewardEpoch {
+    pub start_time: u64,
+    pub duration: u64,
+    /// Rewards the vault received for the epoch, after transfer fees
+    pub amount: u64,
+}
+
+/// Future reward epochs of a pool, in start order. Epochs before `next_epoch`
+/// are already applied to the pool and are dropped when a new one is added.
+pub const REWARD_SCHEDULE_STORAGE_TOTAL_BYTES: usize = 1 + 32 + 1 + 1 + 24 * MAX_REWARD_EPOCHS;
+#[derive(Clone, BorshDeserialize, BorshSerialize, Copy)]
+pub struct RewardSchedule {
+    pub acc_type: u8,
+    pub your_pool: Pubkey,
+    pub next_epoch: u8,
+    pub epoch_count: u8,
+    pub epochs: [RewardEpoch; MAX_REWARD_EPOCHS],
+}
+
+/// Records are only trusted in accounts the program owns, anyone can create
+/// an account with the same layout under another owner.
+fn check_program_account(account: &AccountInfo, program_id: &Pubkey) -> ProgramResult {
+    if account.owner != program_id {
+        msg!("ProgramError::IncorrectProgramId");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+impl YourPool {
+    /// Reads the pool stored in `account`, which has to be owned by `program_id`.
+    /// V1 pools have to go through `MigratePool` first.
+    pub fn load(account: &AccountInfo, program_id: &Pubkey) -> Result<Self, ProgramError> {
+        let pool = Self::load_any_version(account, program_id)?;
+        if pool.acc_type != AccTypesWithVersion::YourPoolDataV2 as u8 {
+            msg!("CustomError::PoolNotMigrated");
+            return Err(CustomError::PoolNotMigrated.into());
+        }
+        Ok(pool)
+    }
+
+    /// `YourPool::load` that also reads V1 pools, for the instructions that
+    /// migrate or only inspect them.
+    pub fn load_any_version(
+        account: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        check_program_account(account, program_id)?;
+        Self::unpack_from_slice(&account.try_borrow_data()?)
+    }
+
+    /// Writes the pool into `account`, which has to hold the current layout.
+    pub fn save(&self, account: &AccountInfo) -> ProgramResult {
+        self.pack_into_slice(&mut account.try_borrow_mut_data()?)
+    }
+
+    /// Address of the pool signer PDA of the pool stored at `pool_storage`,
+    /// from the bump kept in `pda_nonce` instead of searching for it again.
+    pub fn pool_signer_address(
+        &self,
+        pool_storage: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Result<Pubkey, ProgramError> {
+        Pubkey::create_program_address(&[&pool_storage.to_bytes(), &[self.pda_nonce]], program_id)
+            .map_err(|_| ProgramError::InvalidSeeds)
+    }
+
+    /// Checks that `pool_signer_pda` is the pool signer of the pool stored at
+    /// `pool_storage` and returns its address.
+    pub fn check_pool_signer(
+        &self,
+        pool_signer_pda: &AccountInfo,
+        pool_storage: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Result<Pubkey, ProgramError> {
+        let pool_signer_address = self.pool_signer_address(pool_storage, program_id)?;
+        if pool_signer_address != *pool_signer_pda.key {
+            msg!("CustomError::DerivedKeyInvalid");
+            return Err(CustomError::DerivedKeyInvalid.into());
+        }
+        Ok(pool_signer_address)
+    }
+
+    /// Reads a pool of any supported version. V1 pools are widened to the
+    /// current layout with the new fields left at their defaults.
+    pub fn unpack_from_slice(data: &[u8]) -> Result<Self, ProgramError> {
+        let acc_type = *data.first().ok_or(CustomError::DataSizeNotMatched)?;
+        let expected_len = Self::storage_len(acc_type)?;
+        if data.len() != expected_len {
+            msg!("CustomError::DataSizeNotMatched");
+            return Err(CustomError::DataSizeNotMatched.into());
+        }
+        if acc_type == AccTypesWithVersion::YourPoolDataV1 as u8 {
+            return Ok(YourPoolV1::try_from_slice(data)?.into());
+        }
+        Ok(YourPool::try_from_slice(data)?)
+    }
+
+    /// Writes the pool in the current layout. V1 pools are never written back,
+    /// that would drop every field they have no room for.
+    pub fn pack_into_slice(&self, data: &mut [u8]) -> Result<(), ProgramError> {
+        if self.acc_type != AccTypesWithVersion::YourPoolDataV2 as u8 {
+            msg!("CustomError::PoolNotMigrated");
+            return Err(CustomError::PoolNotMigrated.into());
+        }
+        if data.len() != YOUR_POOL_STORAGE_TOTAL_BYTES {
+            msg!("CustomError::DataSizeNotMatched");
+            return Err(CustomError::DataSizeNotMatched.into());
+        }
+        data.copy_from_slice(&self.try_to_vec()?);
+        Ok(())
+    }
+
+    fn storage_len(acc_type: u8) -> Result<usize, ProgramError> {
+        if acc_type == AccTypesWithVersion::YourPoolDataV1 as u8 {
+            Ok(YOUR_POOL_V1_STORAGE_TOTAL_BYTES)
+        } else if acc_type == AccTypesWithVersion::YourPoolDataV2 as u8 {
+            Ok(YOUR_POOL_STORAGE_TOTAL_BYTES)
+        } else {
+            msg!("CustomError::ExpectedAccountTypeMismatched");
+            Err(CustomError::ExpectedAccountTypeMismatched.into())
+        }
+    }
+
+    /// Whether `Stake` mints a receipt token for this pool.
+    pub fn has_receipt_token(&self) -> bool {
+        self.receipt_mint != Pubkey::default()
+    }
+}
+
+impl User {
+    /// Reads the user record stored in `account`, which has to be owned by
+    /// `program_id` and belong to `pool_storage`. V1 records have to go
+    /// through `MigrateUser` first.
+    pub fn load(
+        account: &AccountInfo,
+        program_id: &Pubkey,
+        pool_storage: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let user = Self::load_any_version(account, program_id, pool_storage)?;
+        if user.acc_type != AccTypesWithVersion::UserDataV2 as u8 {
+            msg!("CustomError::UserNotMigrated");
+            return Err(CustomError::UserNotMigrated.into());
+        }
+        Ok(user)
+    }
+
+    /// `User::load` that also reads V1 records, see `YourPool::load_any_version`.
+    pub fn load_any_version(
+        account: &AccountInfo,
+        program_id: &Pubkey,
+        pool_storage: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        check_program_account(account, program_id)?;
+        let user = Self::unpack_from_slice(&account.try_borrow_data()?)?;
+        if user.your_pool != *pool_storage {
+            msg!("CustomError::UserPoolMismatched");
+            return Err(CustomError::UserPoolMismatched.into());
+        }
+        Ok(user)
+    }
+
+    /// `User::load` for the record of `user_wallet`.
+    pub fn load_for_wallet(
+        account: &AccountInfo,
+        program_id: &Pubkey,
+        user_wallet: &Pubkey,
+        pool_storage: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let user = Self::load(account, program_id, pool_storage)?;
+        if user.user_wallet != *user_wallet {
+            msg!("CustomError::UserStorageAuthorityMismatched");
+            return Err(CustomError::UserStorageAuthorityMismatched.into());
+        }
+        Ok(user)
+    }
+
+    /// Writes the user record into `account`, which has to hold the current layout.
+    pub fn save(&self, account: &AccountInfo) -> ProgramResult {
+        self.pack_into_slice(&mut account.try_borrow_mut_data()?)
+    }
+
+    /// Reads a user record of any supported version, see `YourPool::unpack_from_slice`.
+    pub fn unpack_from_slice(data: &[u8]) -> Result<Self, ProgramError> {
+        let acc_type = *data.first().ok_or(CustomError::DataSizeNotMatched)?;
+        let expected_len = Self::storage_len(acc_type)?;
+        if data.len() != expected_len {
+            msg!("CustomError::DataSizeNotMatched");
+            return Err(CustomError::DataSizeNotMatched.into());
+        }
+        if acc_type == AccTypesWithVersion::UserDataV1 as u8 {
+            return Ok(UserV1::try_from_slice(data)?.into());
+        }
+        Ok(User::try_from_slice(data)?)
+    }
+
+    /// Writes the user record in the current layout, see `YourPool::pack_into_slice`.
+    pub fn pack_into_slice(&self, data: &mut [u8]) -> Result<(), ProgramError> {
+        if self.acc_type != AccTypesWithVersion::UserDataV2 as u8 {
+            msg!("CustomError::UserNotMigrated");
+            return Err(CustomError::UserNotMigrated.into());
+        }
+        if data.len() != USER_STORAGE_TOTAL_BYTES {
+            msg!("CustomError::DataSizeNotMatched");
+            return Err(CustomError::DataSizeNotMatched.into());
+        }
+        data.copy_from_slice(&self.try_to_vec()?);
+        Ok(())
+    }
+
+    fn storage_len(acc_type: u8) -> Result<usize, ProgramError> {
+        if acc_type == AccTypesWithVersion::UserDataV1 as u8 {
+            Ok(USER_V1_STORAGE_TOTAL_BYTES)
+        } else if acc_type == AccTypesWithVersion::UserDataV2 as u8 {
+            Ok(USER_STORAGE_TOTAL_BYTES)
+        } else {
+            msg!("CustomError::ExpectedAccountTypeMismatched");
+            Err(CustomError::ExpectedAccountTypeMismatched.into())
+        }
+    }
+}
+
+impl From<YourPoolV1> for YourPool {
+    fn from(pool: YourPoolV1) -> Self {
+        YourPool {
+            acc_type: pool.acc_type,
+            owner_wallet: pool.owner_wallet,
+            your_staking_vault: pool.your_staking_vault,
+            your_reward_rate: pool.your_reward_rate,
+            your_epoch_duration: pool.your_epoch_duration,
+            user_stake_count: pool.user_stake_count,
+            pda_nonce: pool.pda_nonce,
+            reward_duration_end: pool.reward_duration_end,
+            your_staking_mint: Pubkey::default(),
+            your_rewards_mint: Pubkey::default(),
+            your_rewards_vault: Pubkey::default(),
+            migrated_from: Pubkey::default(),
+            pool_index: 0,
+            wind_down_date: 0,
+            total_your_staked: 0,
+            reward_per_token_stored: 0,
+            last_update_time: 0,
+            your_rewards_owed: 0,
+            next_epoch_start: 0,
+            your_rewards_scheduled: 0,
+            emission_mode: EmissionMode::Flat as u8,
+            initial_reward_rate: 0,
+            halving_interval: 0,
+            emission_start: 0,
+            apr_bps: 0,
+            vesting_period: 0,
+            vesting_penalty_bps: 0,
+            your_rewards_vesting: 0,
+            receipt_mint: Pubkey::default(),
+            accepts_stake_migrations: 0,
+            stats: PoolStats::default(),
+            penalty_stream_remaining: 0,
+            penalty_stream_start: 0,
+            penalty_stream_end: 0,
+            reserved: [0u8; 60],
+        }
+    }
+}
+
+impl From<UserV1> for User {
+    fn from(user: UserV1) -> Self {
+        User {
+            acc_type: user.acc_type,
+            user_wallet: user.user_wallet,
+            your_pool: user.your_pool,
+            balance_your_staked: user.balance_your_staked,
+            unstake_pending: user.unstake_pending,
+            unstake_pending_date: user.unstake_pending_date,
+            nonce: user.nonce,
+            claim_timeout_date: user.claim_timeout_date,
+            reward_per_token_complete: 0,
+            reward_per_token_pending: 0,
+            vesting_locked: 0,
+            vesting_start: 0,
+            vesting_end: 0,
+            vesting_unlocked: 0,
+            reserved: [0u8; 102],
+        }
+    }
+}
+
+impl RewardSchedule {
+    /// Reads the reward schedule stored in `account`, which has to be owned
+    /// by `program_id` and belong to `pool_storage`.
+    pub fn load(
+        account: &AccountInfo,
+        program_id: &Pubkey,
+        pool_storage: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        check_program_account(account, program_id)?;
+        let data = account.try_borrow_data()?;
+        if data.len() != REWARD_SCHEDULE_STORAGE_TOTAL_BYTES {
+            msg!("CustomError::DataSizeNotMatched");
+            return Err(CustomError::DataSizeNotMatched.into());
+        }
+        let schedule = Self::try_from_slice(&data)?;
+        if schedule.acc_type != AccTypesWithVersion::RewardScheduleV1 as u8 {
+            msg!("CustomError::ExpectedAccountTypeMismatched");
+            return Err(CustomError::ExpectedAccountTypeMismatched.into());
+        }
+        if schedule.your_pool != *pool_storage
+            || schedule.next_epoch > schedule.epoch_count
+            || schedule.epoch_count as usize > MAX_REWARD_EPOCHS
+        {
+            msg!("CustomError::InvalidRewardSchedule");
+            return Err(CustomError::InvalidRewardSchedule.into());
+        }
+        Ok(schedule)
+    }
+
+    pub fn save(&self, account: &AccountInfo) -> ProgramResult {
+        let mut data = account.try_borrow_mut_data()?;
+        if data.len() != REWARD_SCHEDULE_STORAGE_TOTAL_BYTES {
+            msg!("CustomError::DataSizeNotMatched");
+            return Err(CustomError::DataSizeNotMatched.into());
+        }
+        data.copy_from_slice(&self.try_to_vec()?);
+        Ok(())
+    }
+
+    /// The epoch the pool switches to next, if any is left.
+    pub fn next(&self) -> Option<&RewardEpoch> {
+        if self.next_epoch < self.epoch_count {
+            self.epochs.get(self.next_epoch as usize)
+        } else {
+            None
+        }
+    }
+
+    /// The last scheduled epoch, applied or not.
+    pub fn last(&self) -> Option<&RewardEpoch> {
+        (self.epoch_count as usize)
+            .checked_sub(1)
+            .and_then(|last| self.epochs.get(last))
+    }
+
+    /// Appends `epoch`, making room by dropping the epochs already applied.
+    pub fn push(&mut self, epoch: RewardEpoch) -> ProgramResult {
+        let applied = self.next_epoch as usize;
+        self.epochs.rotate_left(applied);
+        self.epoch_count -= self.next_epoch;
+        self.next_epoch = 0;
+        let slot = self
+            .epochs
+            .get_mut(self.epoch_count as usize)
+            .ok_or(CustomError::RewardScheduleFull)?;
+        *slot = epoch;
+        self.epoch_count += 1;
+        Ok(())
+    }
 }
@@ -0,0 +1,262 @@
+//! Vault helpers that work with both the legacy token program and Token-2022.
+//!
+//! Token-2022 shares the legacy account and mint layouts and appends an
+//! account type byte plus TLV extensions after the 165-byte base. Extensions
+//! are read and instructions built with the `spl_token_2022` crate, whose
+//! builders accept either program id.
+
+use crate::error::CustomError;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, instruction::Instruction, msg,
+    program::invoke, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+};
+use spl_token::state::{Account as TokenAccount, Mint};
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+
+pub use spl_token_2022;
+
+pub mod spl_associated_token_account {
+    solana_program::declare_id!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+}
+
+pub fn is_token_program(key: &Pubkey) -> bool {
+    *key == spl_token::id() || *key == spl_token_2022::id()
+}
+
+pub fn check_token_program(token_program: &AccountInfo) -> ProgramResult {
+    if !is_token_program(token_program.key) {
+        msg!("CustomError::InvalidTokenProgram");
+        return Err(CustomError::InvalidTokenProgram.into());
+    }
+    Ok(())
+}
+
+/// Reads the base layout of a legacy or Token-2022 token account.
+pub fn unpack_account(data: &[u8]) -> Result<TokenAccount, ProgramError> {
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(data)?;
+    TokenAccount::unpack(&data[..TokenAccount::LEN])
+}
+
+/// Reads the base layout of a legacy or Token-2022 mint.
+pub fn unpack_mint(data: &[u8]) -> Result<Mint, ProgramError> {
+    StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data)?;
+    Mint::unpack(&data[..Mint::LEN])
+}
+
+/// Reads the mint of a vault, which has to live under the vault's token program.
+pub fn unpack_vault_mint(
+    mint: &AccountInfo,
+    token_program: &AccountInfo,
+    vault_data: &TokenAccount,
+) -> Result<Mint, ProgramError> {
+    if vault_data.mint != *mint.key || mint.owner != token_program.key {
+        msg!("CustomError::MintMismatched");
+        return Err(CustomError::MintMismatched.into());
+    }
+    unpack_mint(&mint.try_borrow_data()?)
+}
+
+/// Checks that `token_account` is a `mint` account of `wallet` under
+/// `token_program`, so that tokens paid out cannot be redirected.
+pub fn check_user_token_account(
+    token_account: &AccountInfo,
+    token_program: &AccountInfo,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+) -> ProgramResult {
+    if token_account.owner != token_program.key {
+        msg!("CustomError::InvalidUserTokenAccount");
+        return Err(CustomError::InvalidUserTokenAccount.into());
+    }
+    let token_account_data = unpack_account(&token_account.try_borrow_data()?)?;
+    if token_account_data.owner != *wallet || token_account_data.mint != *mint {
+        msg!("CustomError::InvalidUserTokenAccount");
+        return Err(CustomError::InvalidUserTokenAccount.into());
+    }
+    Ok(())
+}
+
+/// Associated token account of `wallet` for `mint` under `token_program`.
+pub fn associated_token_address(wallet: &Pubkey, token_program: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            &wallet.to_bytes(),
+            &token_program.to_bytes(),
+            &mint.to_bytes(),
+        ],
+        &spl_associated_token_account::id(),
+    )
+    .0
+}
+
+/// Size of a vault for `mint`. Mint extensions that would change how vault
+/// balances behave, beyond transfer fees, are rejected.
+pub fn vault_len(mint_data: &[u8]) -> Result<usize, ProgramError> {
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(mint_data)?;
+    let mint_extensions = mint.get_extension_types()?;
+    for extension_type in &mint_extensions {
+        match extension_type {
+            ExtensionType::TransferFeeConfig
+            | ExtensionType::MintCloseAuthority
+            | ExtensionType::InterestBearingConfig
+            | ExtensionType::MetadataPointer
+            | ExtensionType::TokenMetadata => {}
+            _ => {
+                msg!("CustomError::UnsupportedMintExtension");
+                return Err(CustomError::UnsupportedMintExtension.into());
+            }
+        }
+    }
+    ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(
+        &ExtensionType::get_required_init_account_extensions(&mint_extensions),
+    )
+}
+
+pub fn transfer_checked(
+    token_program: &Pubkey,
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Result<Instruction, ProgramError> {
+    spl_token_2022::instruction::transfer_checked(
+        token_program,
+        source,
+        mint,
+        destination,
+        authority,
+        &[],
+        amount,
+        decimals,
+    )
+}
+
+/// Transfers `amount` from `source` into `vault` and returns what the vault
+/// actually received, transfer fees and hooks can make it less than `amount`.
+pub fn deposit<'a>(
+    token_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    vault: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    amount: u64,
+    decimals: u8,
+) -> Result<u64, ProgramError> {
+    let vault_balance_before = unpack_account(&vault.try_borrow_data()?)?.amount;
+    invoke(
+        &transfer_checked(
+            token_program.key,
+            source.key,
+            mint.key,
+            vault.key,
+            authority.key,
+            amount,
+            decimals,
+        )?,
+        &[
+            source.clone(),
+            mint.clone(),
+            vault.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+    )?;
+    let vault_balance_after = unpack_account(&vault.try_borrow_data()?)?.amount;
+    Ok(vault_balance_after
+        .checked_sub(vault_balance_before)
+        .ok_or(CustomError::AmountOverflow)?)
+}
+
+pub fn initialize_account(
+    token_program: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    spl_token_2022::instruction::initialize_account(token_program, account, mint, owner)
+}
+
+pub fn initialize_mint(
+    token_program: &Pubkey,
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    decimals: u8,
+) -> Result<Instruction, ProgramError> {
+    spl_token_2022::instruction::initialize_mint(
+        token_program,
+        mint,
+        mint_authority,
+        None,
+        decimals,
+    )
+}
+
+pub fn mint_to_checked(
+    token_program: &Pubkey,
+    mint: &Pubkey,
+    account: &Pubkey,
+    mint_authority: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Result<Instruction, ProgramError> {
+    spl_token_2022::instruction::mint_to_checked(
+        token_program,
+        mint,
+        account,
+        mint_authority,
+        &[],
+        amount,
+        decimals,
+    )
+}
+
+pub fn burn_checked(
+    token_program: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Result<Instruction, ProgramError> {
+    spl_token_2022::instruction::burn_checked(
+        token_program,
+        account,
+        mint,
+        authority,
+        &[],
+        amount,
+        decimals,
+    )
+}
+
+pub fn close_account(
+    token_program: &Pubkey,
+    account: &Pubkey,
+    destination: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    spl_token_2022::instruction::close_account(token_program, account, destination, owner, &[])
+}
+
+/// Moves transfer fees withheld in `vault` to its mint. Token-2022 refuses to
+/// close an account that still holds withheld fees; harvesting is permissionless.
+pub fn harvest_withheld_tokens<'a>(
+    token_program: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    vault: &AccountInfo<'a>,
+) -> ProgramResult {
+    if *token_program.key != spl_token_2022::id() || vault.data_len() == TokenAccount::LEN {
+        return Ok(());
+    }
+    msg!("Calling the token program to harvest withheld fees to the mint...");
+    invoke(
+        &spl_token_2022::extension::transfer_fee::instruction::harvest_withheld_tokens_to_mint(
+            token_program.key,
+            mint.key,
+            &[vault.key],
+        )?,
+        &[mint.clone(), vault.clone(), token_program.clone()],
+    )
+}
@@ -2,21 +2,37 @@ use std::cell::RefMut;
 use std::convert::TryInto;
 
 use crate::error::CustomError;
-use solana_program::account_info::AccountInfo;
+use crate::event::{StakingEvent, StakingEventKind};
+use crate::state::{AccTypesWithVersion, EmissionMode, RewardSchedule, User, YourPool};
+use crate::token;
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::clock::Clock;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
 use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::sysvar::Sysvar;
 
 // to avoid rounding errors
 const PRECISION: u128 = u64::MAX as u128;
 
 pub mod constants {
     pub const MIN_DURATION: u64 = 86400; // 1 day
+    pub const WIND_DOWN_GRACE_PERIOD: i64 = 14 * 86400; // 2 weeks
+    pub const SECONDS_PER_YEAR: u64 = 365 * 86400;
+    pub const BPS_DENOMINATOR: u64 = 10_000;
 }
 
+/// Moves all lamports of `account_to_close` to `sol_receiving_account`. The
+/// data is wiped first, so an account refunded within the same transaction
+/// no longer unpacks as a pool or user record.
 pub fn close_account(
     account_to_close: &AccountInfo,
     sol_receiving_account: &AccountInfo,
     account_to_close_data_byte_array: &mut RefMut<&mut [u8]>,
 ) -> Result<(), CustomError> {
+    account_to_close_data_byte_array.fill(0);
     **sol_receiving_account.lamports.borrow_mut() = sol_receiving_account
         .lamports()
         .checked_add(account_to_close.lamports())
@@ -26,23 +42,159 @@ pub fn close_account(
     Ok(())
 }
 
+/// Closes a pool storage account down to a one byte `ClosedPool` record, so
+/// that its address can never be initialized as a pool again. Lamports above
+/// the rent exempt minimum of that byte go to `sol_receiving_account`.
+pub fn close_pool_account(
+    pool_storage_account: &AccountInfo,
+    sol_receiving_account: &AccountInfo,
+) -> ProgramResult {
+    pool_storage_account.try_borrow_mut_data()?.fill(0);
+    pool_storage_account.realloc(1, false)?;
+    pool_storage_account.try_borrow_mut_data()?[0] = AccTypesWithVersion::ClosedPool as u8;
+
+    let refund = pool_storage_account
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(1));
+    **sol_receiving_account.lamports.borrow_mut() = sol_receiving_account
+        .lamports()
+        .checked_add(refund)
+        .ok_or(CustomError::AmountOverflow)?;
+    **pool_storage_account.lamports.borrow_mut() -= refund;
+    Ok(())
+}
+
+/// Reward rate of a pool over time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EmissionCurve {
+    Flat {
+        rate: u64,
+    },
+    /// `initial_rate` from `start` on, halved every `interval`
+    Halving {
+        initial_rate: u64,
+        start: u64,
+        interval: u64,
+    },
+    /// `apr_bps` a year on every staked token, so the rate follows the stake
+    FixedApr {
+        apr_bps: u64,
+        total_staked: u64,
+    },
+}
+
+impl EmissionCurve {
+    pub fn of(pool: &YourPool) -> Self {
+        if pool.emission_mode == EmissionMode::Halving as u8 {
+            EmissionCurve::Halving {
+                initial_rate: pool.initial_reward_rate,
+                start: pool.emission_start,
+                interval: pool.halving_interval,
+            }
+        } else if pool.emission_mode == EmissionMode::FixedApr as u8 {
+            EmissionCurve::FixedApr {
+                apr_bps: pool.apr_bps,
+                total_staked: pool.total_your_staked,
+            }
+        } else {
+            EmissionCurve::Flat {
+                rate: pool.your_reward_rate,
+            }
+        }
+    }
+
+    /// Rate in effect at `time`.
+    pub fn rate_at(&self, time: u64) -> u64 {
+        match *self {
+            EmissionCurve::Flat { rate } => rate,
+            EmissionCurve::Halving {
+                initial_rate,
+                start,
+                interval,
+            } => {
+                if time < start || interval == 0 {
+                    return 0;
+                }
+                let halvings = (time - start) / interval;
+                initial_rate.checked_shr(halvings as u32).unwrap_or(0)
+            }
+            EmissionCurve::FixedApr {
+                apr_bps,
+                total_staked,
+            } => {
+                // rounded down, `emitted` integrates without the rounding
+                let rate = (total_staked as u128) * (apr_bps as u128)
+                    / (constants::BPS_DENOMINATOR as u128)
+                    / (constants::SECONDS_PER_YEAR as u128);
+                rate.try_into().unwrap_or(u64::MAX)
+            }
+        }
+    }
+
+    /// Rewards emitted between `from` and `to`, integrating the rate over
+    /// every halving interval the window touches.
+    pub fn emitted(&self, from: u64, to: u64) -> Result<u128, ProgramError> {
+        let window = to.checked_sub(from).ok_or(CustomError::AmountOverflow)?;
+        let (start, interval) = match *self {
+            EmissionCurve::Flat { rate } => {
+                return (rate as u128)
+                    .checked_mul(window as u128)
+                    .ok_or_else(|| CustomError::AmountOverflow.into())
+            }
+            EmissionCurve::Halving {
+                start, interval, ..
+            } => (start, interval),
+            EmissionCurve::FixedApr {
+                apr_bps,
+                total_staked,
+            } => {
+                return (total_staked as u128)
+                    .checked_mul(apr_bps as u128)
+                    .and_then(|yearly| yearly.checked_mul(window as u128))
+                    .map(|emitted| {
+                        emitted
+                            / (constants::BPS_DENOMINATOR as u128)
+                            / (constants::SECONDS_PER_YEAR as u128)
+                    })
+                    .ok_or_else(|| CustomError::AmountOverflow.into())
+            }
+        };
+        let mut emitted = 0u128;
+        let mut time = from.max(start);
+        // at most 64 intervals before the rate reaches zero
+        while time < to {
+            let rate = self.rate_at(time);
+            if rate == 0 {
+                break;
+            }
+            let halvings = (time - start) / interval;
+            let interval_end = halvings
+                .checked_add(1)
+                .and_then(|next| next.checked_mul(interval))
+                .and_then(|offset| offset.checked_add(start))
+                .unwrap_or(u64::MAX)
+                .min(to);
+            emitted = emitted
+                .checked_add((rate as u128) * ((interval_end - time) as u128))
+                .ok_or(CustomError::AmountOverflow)?;
+            time = interval_end;
+        }
+        Ok(emitted)
+    }
+}
+
 pub fn rewards_per_token(
     total_your_staked: u64,
     last_time_reward_applicable: u64,
     total_stake_last_update_time: u64,
-    your_reward_rate: u64,
+    emission_curve: &EmissionCurve,
     your_reward_per_token_stored: u128,
 ) -> Result<u128, ProgramError> {
     if total_your_staked == 0 {
         return Ok(your_reward_per_token_stored);
     }
-    let new_reward_per_token_stored: u128 = (your_reward_rate as u128)
-        .checked_mul(
-            (last_time_reward_applicable as u128)
-                .checked_sub(total_stake_last_update_time as u128)
-                .ok_or(CustomError::AmountOverflow)?,
-        )
-        .ok_or(CustomError::AmountOverflow)?;
+    let new_reward_per_token_stored: u128 =
+        emission_curve.emitted(total_stake_last_update_time, last_time_reward_applicable)?;
     let new_reward_per_token_stored_with_precision: u128 = new_reward_per_token_stored
         .checked_mul(PRECISION)
         .ok_or(CustomError::AmountOverflow)?;
@@ -53,7 +205,7 @@ pub fn rewards_per_token(
                 .ok_or(CustomError::AmountOverflow)?,
         )
         .ok_or(CustomError::AmountOverflow)?;
-    return Ok(updated_rewards_per_token_stored);
+    Ok(updated_rewards_per_token_stored)
 }
 
 pub fn earned(
@@ -73,10 +225,404 @@ pub fn earned(
     let updated_reward_per_token_pending = reward_per_token_pending
         .checked_add(mul)
         .ok_or(CustomError::AmountOverflow)?;
-    return Ok(updated_reward_per_token_pending);
+    Ok(updated_reward_per_token_pending)
 }
 
 pub fn last_time_reward_applicable(reward_duration_end: u64, now_unix_timestamp: i64) -> u64 {
-    return std::cmp::min(now_unix_timestamp.try_into().unwrap(), reward_duration_end);
+    std::cmp::min(
+        now_unix_timestamp.try_into().unwrap_or(0),
+        reward_duration_end,
+    )
+}
+
+/// Brings the reward checkpoint of `pool` up to `now` and, when given, settles
+/// what `user` earned since their own checkpoint into their pending rewards.
+/// Has to run before anything changes a staked balance or the reward rate.
+pub fn update_rewards(
+    pool: &mut YourPool,
+    user: Option<&mut User>,
+    now_unix_timestamp: i64,
+) -> Result<(), ProgramError> {
+    let applicable = last_time_reward_applicable(pool.reward_duration_end, now_unix_timestamp)
+        .max(pool.last_update_time);
+    let emission_curve = EmissionCurve::of(pool);
+    if pool.total_your_staked != 0 {
+        let emitted: u64 = emission_curve
+            .emitted(pool.last_update_time, applicable)?
+            .try_into()
+            .map_err(|_| CustomError::AmountOverflow)?;
+        pool.your_rewards_owed = pool
+            .your_rewards_owed
+            .checked_add(emitted)
+            .ok_or(CustomError::AmountOverflow)?;
+    }
+    pool.reward_per_token_stored = rewards_per_token(
+        pool.total_your_staked,
+        applicable,
+        pool.last_update_time,
+        &emission_curve,
+        pool.reward_per_token_stored,
+    )?;
+    pool.last_update_time = applicable;
+    pool.your_reward_rate = emission_curve.rate_at(applicable);
+    stream_penalties(pool, now_unix_timestamp)?;
+
+    if let Some(user) = user {
+        user.reward_per_token_pending = earned(
+            user.balance_your_staked,
+            pool.reward_per_token_stored,
+            user.reward_per_token_complete,
+            user.reward_per_token_pending,
+        )?;
+        user.reward_per_token_complete = pool.reward_per_token_stored;
+    }
+    Ok(())
+}
+
+/// Starts a reward period whose emission curve `emission` sets on the pool
+/// for what the rewards vault received out of `amount` and the current time,
+/// returning the length of the period. Shared by `StartHalvingEmission` and
+/// `StartFixedApr`, which take the same accounts. The vault has to cover
+/// every reward the pool is committed to, including the new period.
+pub fn start_emission<F>(
+    accounts: &[AccountInfo],
+    amount: u64,
+    program_id: &Pubkey,
+    kind: StakingEventKind,
+    emission: F,
+) -> ProgramResult
+where
+    F: FnOnce(&mut YourPool, u64, u64) -> Result<u64, ProgramError>,
+{
+    let account_info_iter = &mut accounts.iter();
+    let pool_owner_wallet_account = next_account_info(account_info_iter)?;
+    let your_pool_storage_account = next_account_info(account_info_iter)?;
+    let your_rewards_vault = next_account_info(account_info_iter)?;
+    let your_rewards_ata_to_debit = next_account_info(account_info_iter)?;
+    let your_rewards_mint = next_account_info(account_info_iter)?;
+    let rewards_token_program = next_account_info(account_info_iter)?;
+
+    if !pool_owner_wallet_account.is_signer {
+        msg!("ProgramError::MissingRequiredSignature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    token::check_token_program(rewards_token_program)?;
+
+    let mut your_pool_data = YourPool::load(your_pool_storage_account, program_id)?;
+    if your_pool_data.owner_wallet != *pool_owner_wallet_account.key {
+        msg!("CustomError::PoolOwnerMismatched");
+        return Err(CustomError::PoolOwnerMismatched.into());
+    }
+    if your_pool_data.wind_down_date != 0 {
+        msg!("CustomError::PoolWindingDown");
+        return Err(CustomError::PoolWindingDown.into());
+    }
+
+    if your_rewards_vault.owner != rewards_token_program.key {
+        msg!("CustomError::AccountOwnerShouldBeTokenProgram");
+        return Err(CustomError::AccountOwnerShouldBeTokenProgram.into());
+    }
+    if your_pool_data.your_rewards_vault != *your_rewards_vault.key {
+        msg!("CustomError::InvalidRewardsVault");
+        return Err(CustomError::InvalidRewardsVault.into());
+    }
+    let your_rewards_vault_data = token::unpack_account(&your_rewards_vault.try_borrow_data()?)?;
+    let your_rewards_mint_data = token::unpack_vault_mint(
+        your_rewards_mint,
+        rewards_token_program,
+        &your_rewards_vault_data,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    if your_pool_data.reward_duration_end >= (now as u64) || your_pool_data.next_epoch_start != 0 {
+        msg!("CustomError::PoolStillActive");
+        return Err(CustomError::PoolStillActive.into());
+    }
+    update_rewards(&mut your_pool_data, None, now)?;
+
+    let amount_received = if amount > 0 {
+        msg!("Calling the token program to transfer YOUR rewards to Rewards Vault...");
+        token::deposit(
+            rewards_token_program,
+            your_rewards_ata_to_debit,
+            your_rewards_mint,
+            your_rewards_vault,
+            pool_owner_wallet_account,
+            amount,
+            your_rewards_mint_data.decimals,
+        )?
+    } else {
+        0u64
+    };
+
+    let duration = emission(&mut your_pool_data, amount_received, now as u64)?;
+    your_pool_data.your_epoch_duration = duration;
+    your_pool_data.reward_duration_end = (now as u64)
+        .checked_add(duration)
+        .ok_or(CustomError::AmountOverflow)?;
+    your_pool_data.last_update_time = now as u64;
+    your_pool_data.your_reward_rate = EmissionCurve::of(&your_pool_data).rate_at(now as u64);
+
+    let projected_obligations = projected_obligations(&your_pool_data, now)?;
+    let rewards_available = your_rewards_vault_data
+        .amount
+        .checked_add(amount_received)
+        .ok_or(CustomError::AmountOverflow)?;
+    msg!("projected_obligations: {}", projected_obligations);
+    msg!("rewards_available: {}", rewards_available);
+    if projected_obligations > rewards_available {
+        msg!("CustomError::InsufficientRewardsForApr");
+        return Err(CustomError::InsufficientRewardsForApr.into());
+    }
+
+    your_pool_data.stats.rewards_funded = your_pool_data
+        .stats
+        .rewards_funded
+        .saturating_add(amount_received);
+    your_pool_data.stats.last_activity_date = now;
+    your_pool_data.save(your_pool_storage_account)?;
+
+    StakingEvent {
+        kind,
+        pool: *your_pool_storage_account.key,
+        user: *pool_owner_wallet_account.key,
+        amount: amount_received,
+        balance_your_staked: 0u64,
+        unstake_pending: 0u64,
+        timestamp: now,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+/// Switches `pool` to every scheduled epoch that started by `now`, bringing
+/// the checkpoint up to each epoch start under the rate it replaces. Time
+/// between two epochs emits nothing. Pools winding down keep their schedule
+/// from ever starting.
+pub fn advance_reward_schedule(
+    pool: &mut YourPool,
+    schedule: &mut RewardSchedule,
+    now_unix_timestamp: i64,
+) -> Result<(), ProgramError> {
+    if pool.wind_down_date != 0 {
+        return Ok(());
+    }
+    while let Some(epoch) = schedule.next().copied() {
+        if epoch.start_time > now_unix_timestamp as u64 {
+            break;
+        }
+        update_rewards(pool, None, epoch.start_time as i64)?;
+        pool.emission_mode = EmissionMode::Flat as u8;
+        pool.your_reward_rate = epoch
+            .amount
+            .checked_div(epoch.duration)
+            .ok_or(CustomError::AmountOverflow)?;
+        pool.your_epoch_duration = epoch.duration;
+        pool.reward_duration_end = epoch
+            .start_time
+            .checked_add(epoch.duration)
+            .ok_or(CustomError::AmountOverflow)?;
+        pool.last_update_time = pool.last_update_time.max(epoch.start_time);
+        pool.your_rewards_scheduled = pool
+            .your_rewards_scheduled
+            .checked_sub(epoch.amount)
+            .ok_or(CustomError::AmountOverflow)?;
+        schedule.next_epoch += 1;
+    }
+    pool.next_epoch_start = schedule.next().map_or(0, |epoch| epoch.start_time);
+    Ok(())
+}
+
+/// Rewards `pool` still has to pay out if nothing changes before the end of
+/// the reward period: what is owed, vesting or streaming, what the rest of
+/// the period will emit at the current stake and what is funded for
+/// scheduled epochs.
+pub fn projected_obligations(
+    pool: &YourPool,
+    now_unix_timestamp: i64,
+) -> Result<u64, ProgramError> {
+    let now = now_unix_timestamp
+        .try_into()
+        .unwrap_or(0)
+        .max(pool.last_update_time);
+    let remaining: u64 = if now < pool.reward_duration_end {
+        EmissionCurve::of(pool)
+            .emitted(now, pool.reward_duration_end)?
+            .try_into()
+            .map_err(|_| CustomError::AmountOverflow)?
+    } else {
+        0
+    };
+    Ok(pool
+        .your_rewards_owed
+        .checked_add(pool.your_rewards_scheduled)
+        .and_then(|committed| committed.checked_add(pool.your_rewards_vesting))
+        .and_then(|committed| committed.checked_add(pool.penalty_stream_remaining))
+        .and_then(|committed| committed.checked_add(remaining))
+        .ok_or(CustomError::AmountOverflow)?)
+}
+
+/// Moves the part of the locked rewards of `user` that vested by `now` to
+/// `vesting_unlocked`. What stays locked keeps unlocking linearly until
+/// `vesting_end`.
+pub fn settle_vesting(user: &mut User, now_unix_timestamp: i64) -> Result<(), ProgramError> {
+    if user.vesting_locked == 0 || now_unix_timestamp <= user.vesting_start {
+        return Ok(());
+    }
+    let vested = if now_unix_timestamp >= user.vesting_end {
+        user.vesting_locked
+    } else {
+        let elapsed = now_unix_timestamp
+            .checked_sub(user.vesting_start)
+            .ok_or(CustomError::AmountOverflow)?;
+        let remaining = user
+            .vesting_end
+            .checked_sub(user.vesting_start)
+            .ok_or(CustomError::AmountOverflow)?;
+        ((user.vesting_locked as u128) * (elapsed as u128) / (remaining as u128)) as u64
+    };
+    user.vesting_locked = user
+        .vesting_locked
+        .checked_sub(vested)
+        .ok_or(CustomError::AmountOverflow)?;
+    user.vesting_unlocked = user
+        .vesting_unlocked
+        .checked_add(vested)
+        .ok_or(CustomError::AmountOverflow)?;
+    user.vesting_start = now_unix_timestamp;
+    Ok(())
+}
+
+/// Locks `amount` of claimed rewards for `user` for `vesting_period`. What is
+/// still locked from earlier claims keeps its remaining time: the new end is
+/// the amount-weighted average of both schedules.
+pub fn vest_rewards(
+    user: &mut User,
+    amount: u64,
+    vesting_period: u64,
+    now_unix_timestamp: i64,
+) -> Result<(), ProgramError> {
+    settle_vesting(user, now_unix_timestamp)?;
+    let locked_remaining = if user.vesting_locked == 0 {
+        0u128
+    } else {
+        user.vesting_end.saturating_sub(now_unix_timestamp).max(0) as u128
+    };
+    let vesting_locked = user
+        .vesting_locked
+        .checked_add(amount)
+        .ok_or(CustomError::AmountOverflow)?;
+    if vesting_locked == 0 {
+        return Ok(());
+    }
+    let remaining = (user.vesting_locked as u128)
+        .checked_mul(locked_remaining)
+        .and_then(|weighted| {
+            weighted.checked_add((amount as u128).checked_mul(vesting_period as u128)?)
+        })
+        .ok_or(CustomError::AmountOverflow)?
+        / (vesting_locked as u128);
+    user.vesting_locked = vesting_locked;
+    user.vesting_start = now_unix_timestamp;
+    user.vesting_end = now_unix_timestamp
+        .checked_add(
+            remaining
+                .try_into()
+                .map_err(|_| CustomError::AmountOverflow)?,
+        )
+        .ok_or(CustomError::AmountOverflow)?;
+    Ok(())
+}
+
+/// Hands `amount` that already sits in the rewards vault to the stakers by
+/// streaming it, together with what is still streaming, until the end of
+/// the reward period and at least `MIN_DURATION`. A stake that only joins
+/// around the forfeit thus earns just its share of that time. `pool` has to
+/// be updated to `now` first.
+pub fn redistribute_rewards(
+    pool: &mut YourPool,
+    amount: u64,
+    now_unix_timestamp: i64,
+) -> Result<(), ProgramError> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let now: u64 = now_unix_timestamp.try_into().unwrap_or(0);
+    pool.penalty_stream_remaining = pool
+        .penalty_stream_remaining
+        .checked_add(amount)
+        .ok_or(CustomError::AmountOverflow)?;
+    pool.penalty_stream_start = now;
+    pool.penalty_stream_end = now
+        .checked_add(constants::MIN_DURATION)
+        .ok_or(CustomError::AmountOverflow)?
+        .max(pool.reward_duration_end)
+        .max(pool.penalty_stream_end);
+    Ok(())
 }
 
+/// Pays the stakers the part of the penalty stream due by `now`, linearly
+/// until `penalty_stream_end`. Without stakers it stays in the vault for
+/// `RecoverRewards`.
+fn stream_penalties(pool: &mut YourPool, now_unix_timestamp: i64) -> Result<(), ProgramError> {
+    let now: u64 = now_unix_timestamp.try_into().unwrap_or(0);
+    if pool.penalty_stream_remaining == 0 || now <= pool.penalty_stream_start {
+        return Ok(());
+    }
+    let streamed = if now >= pool.penalty_stream_end {
+        pool.penalty_stream_remaining
+    } else {
+        ((pool.penalty_stream_remaining as u128) * ((now - pool.penalty_stream_start) as u128)
+            / ((pool.penalty_stream_end - pool.penalty_stream_start) as u128)) as u64
+    };
+    pool.penalty_stream_remaining -= streamed;
+    pool.penalty_stream_start = now;
+    if pool.total_your_staked == 0 || streamed == 0 {
+        return Ok(());
+    }
+    pool.reward_per_token_stored = pool
+        .reward_per_token_stored
+        .checked_add(
+            (streamed as u128)
+                .checked_mul(PRECISION)
+                .ok_or(CustomError::AmountOverflow)?
+                / (pool.total_your_staked as u128),
+        )
+        .ok_or(CustomError::AmountOverflow)?;
+    pool.your_rewards_owed = pool
+        .your_rewards_owed
+        .checked_add(streamed)
+        .ok_or(CustomError::AmountOverflow)?;
+    Ok(())
+}
+
+/// Settles both users and moves the position of `from` to `to` as described
+/// for `TransferPosition`, returning the balance moved.
+pub fn move_position(
+    pool: &mut YourPool,
+    from: &mut User,
+    to: &mut User,
+    now_unix_timestamp: i64,
+) -> Result<u64, ProgramError> {
+    update_rewards(pool, Some(from), now_unix_timestamp)?;
+    update_rewards(pool, Some(to), now_unix_timestamp)?;
+    let amount = from.balance_your_staked;
+    if to.balance_your_staked != 0 && amount != 0 {
+        pool.stats.active_stakers = pool.stats.active_stakers.saturating_sub(1);
+    }
+    to.balance_your_staked = to
+        .balance_your_staked
+        .checked_add(amount)
+        .ok_or(CustomError::AmountOverflow)?;
+    to.reward_per_token_pending = to
+        .reward_per_token_pending
+        .checked_add(from.reward_per_token_pending)
+        .ok_or(CustomError::AmountOverflow)?;
+    // Otherwise moving the rewards would skip the claim timeout
+    to.claim_timeout_date = to.claim_timeout_date.max(from.claim_timeout_date);
+    from.balance_your_staked = 0u64;
+    from.reward_per_token_pending = 0u64;
+    from.unstake_pending = 0u64;
+    Ok(amount)
+}
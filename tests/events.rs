@@ -0,0 +1,78 @@
+use borsh::BorshSerialize;
+use solana_program::pubkey::Pubkey;
+use your_staking::event::{
+    StakingEvent, StakingEventKind, EVENT_DISCRIMINATOR, PROGRAM_DATA_LOG_PREFIX,
+};
+
+fn event() -> StakingEvent {
+    StakingEvent {
+        kind: StakingEventKind::Stake,
+        pool: Pubkey::new_unique(),
+        user: Pubkey::new_unique(),
+        amount: 1_000,
+        balance_your_staked: 3_000,
+        unstake_pending: 0,
+        timestamp: 1_700_000_000,
+    }
+}
+
+/// Formats the fields the way the runtime logs `sol_log_data`.
+fn program_data_log(fields: &[&[u8]]) -> String {
+    let encoded: Vec<String> = fields.iter().map(base64::encode).collect();
+    format!("{}{}", PROGRAM_DATA_LOG_PREFIX, encoded.join(" "))
+}
+
+#[test]
+fn decodes_program_data_logs() {
+    let event = event();
+    let data = event.try_to_vec().unwrap();
+    let log = program_data_log(&[&EVENT_DISCRIMINATOR, &data]);
+
+    assert_eq!(StakingEvent::decode_log(&log), Some(event));
+}
+
+#[test]
+fn ignores_foreign_logs() {
+    let data = event().try_to_vec().unwrap();
+
+    assert_eq!(
+        StakingEvent::decode_log("Program log: Instruction::Stake"),
+        None
+    );
+    assert_eq!(StakingEvent::decode_log(&program_data_log(&[&data])), None);
+    assert_eq!(
+        StakingEvent::decode_log(&program_data_log(&[b"otherprg", &data])),
+        None
+    );
+    assert_eq!(
+        StakingEvent::decode_log(&program_data_log(&[&EVENT_DISCRIMINATOR, &data[..10]])),
+        None
+    );
+}
+
+#[test]
+fn event_kind_tags_are_stable() {
+    let kinds = [
+        StakingEventKind::Stake,
+        StakingEventKind::Unstake,
+        StakingEventKind::FinalUnstake,
+        StakingEventKind::ClaimRewards,
+        StakingEventKind::FundPool,
+        StakingEventKind::ClosePool,
+        StakingEventKind::CloseUser,
+        StakingEventKind::WindDownPool,
+        StakingEventKind::ForceReturnStake,
+        StakingEventKind::RecoverRewards,
+        StakingEventKind::AddRewardEpoch,
+        StakingEventKind::StartHalvingEmission,
+        StakingEventKind::StartFixedApr,
+        StakingEventKind::WithdrawVested,
+        StakingEventKind::RedeemReceipt,
+        StakingEventKind::TransferPosition,
+        StakingEventKind::MigrateStakeOut,
+        StakingEventKind::MigrateStakeIn,
+    ];
+    for (tag, kind) in kinds.iter().enumerate() {
+        assert_eq!(kind.try_to_vec().unwrap(), vec![tag as u8]);
+    }
+}
@@ -0,0 +1,548 @@
+//! Property tests for the reward accrual math in `utils`.
+//!
+//! The harness replays random sequences of stake / unstake / claim actions
+//! across several users against a model pool that settles rewards with
+//! `utils::rewards_per_token` and `utils::earned` before every balance change,
+//! the same way the processors are expected to.
+use borsh::BorshDeserialize;
+use proptest::prelude::*;
+use your_staking::{
+    state::{
+        AccTypesWithVersion, EmissionMode, RewardEpoch, RewardSchedule, User, YourPool,
+        MAX_REWARD_EPOCHS, USER_STORAGE_TOTAL_BYTES, YOUR_POOL_STORAGE_TOTAL_BYTES,
+    },
+    utils,
+};
+
+const MAX_USERS: usize = 8;
+
+#[derive(Clone, Debug)]
+enum Action {
+    Stake { user: usize, amount: u64 },
+    Unstake { user: usize, amount: u64 },
+    Claim { user: usize },
+}
+
+#[derive(Clone, Debug)]
+struct Step {
+    elapsed: u64,
+    action: Action,
+}
+
+#[derive(Clone, Copy, Default)]
+struct UserModel {
+    balance: u64,
+    reward_per_token_complete: u128,
+    reward_per_token_pending: u64,
+    paid: u64,
+    // exact share of the emission, accumulated per settled interval
+    ideal_floor: u128,
+    ideal_intervals: u64,
+}
+
+struct PoolModel {
+    now: u64,
+    reward_rate: u64,
+    reward_duration_end: u64,
+    total_staked: u64,
+    last_update_time: u64,
+    reward_per_token_stored: u128,
+    users: Vec<UserModel>,
+}
+
+impl PoolModel {
+    fn new(reward_rate: u64, reward_duration: u64, users: usize) -> Self {
+        PoolModel {
+            now: 0,
+            reward_rate,
+            reward_duration_end: reward_duration,
+            total_staked: 0,
+            last_update_time: 0,
+            reward_per_token_stored: 0,
+            users: vec![UserModel::default(); users],
+        }
+    }
+
+    fn funded(&self) -> u64 {
+        self.reward_rate * self.reward_duration_end
+    }
+
+    /// Advances the pool checkpoint, tracking the exact per-user share of
+    /// what was emitted since the previous checkpoint.
+    fn update_pool(&mut self) -> Result<(), TestCaseError> {
+        let applicable =
+            utils::last_time_reward_applicable(self.reward_duration_end, self.now as i64);
+        let applicable = applicable.max(self.last_update_time);
+        let window = applicable - self.last_update_time;
+        if self.total_staked != 0 && window != 0 {
+            let emitted = self.reward_rate as u128 * window as u128;
+            for user in self.users.iter_mut().filter(|u| u.balance != 0) {
+                user.ideal_floor += user.balance as u128 * emitted / self.total_staked as u128;
+                user.ideal_intervals += 1;
+            }
+        }
+        self.reward_per_token_stored = utils::rewards_per_token(
+            self.total_staked,
+            applicable,
+            self.last_update_time,
+            &utils::EmissionCurve::Flat {
+                rate: self.reward_rate,
+            },
+            self.reward_per_token_stored,
+        )
+        .map_err(|e| TestCaseError::fail(format!("rewards_per_token failed: {:?}", e)))?;
+        self.last_update_time = applicable;
+        Ok(())
+    }
+
+    fn update_user(&mut self, index: usize) -> Result<(), TestCaseError> {
+        self.update_pool()?;
+        let stored = self.reward_per_token_stored;
+        let user = &mut self.users[index];
+        user.reward_per_token_pending = utils::earned(
+            user.balance,
+            stored,
+            user.reward_per_token_complete,
+            user.reward_per_token_pending,
+        )
+        .map_err(|e| TestCaseError::fail(format!("earned failed: {:?}", e)))?;
+        user.reward_per_token_complete = stored;
+        Ok(())
+    }
+
+    fn apply(&mut self, step: &Step) -> Result<(), TestCaseError> {
+        self.now += step.elapsed;
+        match step.action {
+            Action::Stake { user, amount } => {
+                self.update_user(user)?;
+                self.users[user].balance += amount;
+                self.total_staked += amount;
+            }
+            Action::Unstake { user, amount } => {
+                self.update_user(user)?;
+                let amount = amount.min(self.users[user].balance);
+                self.users[user].balance -= amount;
+                self.total_staked -= amount;
+            }
+            Action::Claim { user } => {
+                self.update_user(user)?;
+                let user = &mut self.users[user];
+                user.paid += user.reward_per_token_pending;
+                user.reward_per_token_pending = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Settles every user at the end of the run so that `paid` holds the
+    /// full amount each user could ever withdraw.
+    fn settle_all(&mut self) -> Result<(), TestCaseError> {
+        self.now = self.now.max(self.reward_duration_end);
+        for index in 0..self.users.len() {
+            self.apply(&Step {
+                elapsed: 0,
+                action: Action::Claim { user: index },
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn action_strategy(users: usize, max_amount: u64) -> impl Strategy<Value = Action> {
+    prop_oneof![
+        (0..users, 1..=max_amount).prop_map(|(user, amount)| Action::Stake { user, amount }),
+        (0..users, 1..=max_amount).prop_map(|(user, amount)| Action::Unstake { user, amount }),
+        (0..users).prop_map(|user| Action::Claim { user }),
+    ]
+}
+
+fn steps_strategy(
+    users: usize,
+    max_amount: u64,
+    max_elapsed: u64,
+) -> impl Strategy<Value = Vec<Step>> {
+    prop::collection::vec(
+        (0..=max_elapsed, action_strategy(users, max_amount))
+            .prop_map(|(elapsed, action)| Step { elapsed, action }),
+        1..64,
+    )
+}
+
+fn check_run(
+    reward_rate: u64,
+    reward_duration: u64,
+    users: usize,
+    steps: &[Step],
+) -> Result<(), TestCaseError> {
+    let mut pool = PoolModel::new(reward_rate, reward_duration, users);
+    for step in steps {
+        pool.apply(step)?;
+    }
+    pool.settle_all()?;
+
+    let total_paid: u128 = pool.users.iter().map(|u| u.paid as u128).sum();
+    prop_assert!(
+        total_paid <= pool.funded() as u128,
+        "paid {} out of {} funded",
+        total_paid,
+        pool.funded()
+    );
+
+    for (index, user) in pool.users.iter().enumerate() {
+        let paid = user.paid as u128;
+        // each settled interval floors twice: once in the per-token rate and
+        // once when multiplying back by the balance
+        let max_dust = 2 * user.ideal_intervals as u128;
+        prop_assert!(
+            paid <= user.ideal_floor + user.ideal_intervals as u128,
+            "user {} paid {} above exact share {}",
+            index,
+            paid,
+            user.ideal_floor
+        );
+        prop_assert!(
+            paid + max_dust >= user.ideal_floor,
+            "user {} lost {} to rounding over {} intervals",
+            index,
+            user.ideal_floor - paid,
+            user.ideal_intervals
+        );
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    /// Token amounts with 9 decimals, up to 100 whole reward tokens emitted
+    /// per second and epochs between one day and one year.
+    #[test]
+    fn realistic_pools_never_overpay(
+        users in 1..=MAX_USERS,
+        reward_duration in utils::constants::MIN_DURATION..=365 * 86400u64,
+        reward_rate in 1..=100_000_000_000u64,
+        steps in steps_strategy(MAX_USERS, 1_000_000_000_000_000, 7 * 86400),
+    ) {
+        let steps: Vec<Step> = steps.into_iter().map(|mut step| {
+            match &mut step.action {
+                Action::Stake { user, .. }
+                | Action::Unstake { user, .. }
+                | Action::Claim { user } => *user %= users,
+            }
+            step
+        }).collect();
+        check_run(reward_rate, reward_duration, users, &steps)?;
+    }
+
+    /// Tiny balances next to a large stake are where the per-token rounding
+    /// is most visible.
+    #[test]
+    fn dust_is_bounded_for_small_stakers(
+        whale in 1_000_000_000_000u64..=u32::MAX as u64 * 1_000_000,
+        reward_rate in 1..=1_000u64,
+        steps in steps_strategy(MAX_USERS, 1_000, 3600),
+    ) {
+        let mut seeded = vec![Step { elapsed: 0, action: Action::Stake { user: 0, amount: whale } }];
+        seeded.extend(steps.into_iter().filter(|step| match step.action {
+            Action::Stake { user, .. } | Action::Unstake { user, .. } | Action::Claim { user } => user != 0,
+        }));
+        check_run(reward_rate, utils::constants::MIN_DURATION, MAX_USERS, &seeded)?;
+    }
+}
+
+#[test]
+fn rewards_per_token_is_unchanged_without_stake() {
+    let stored = 42u128;
+    assert_eq!(
+        utils::rewards_per_token(
+            0,
+            1_000,
+            0,
+            &utils::EmissionCurve::Flat { rate: 1_000_000 },
+            stored
+        )
+        .unwrap(),
+        stored
+    );
+}
+
+#[test]
+fn single_staker_receives_whole_emission() {
+    let mut pool = PoolModel::new(1_000, utils::constants::MIN_DURATION, 1);
+    pool.apply(&Step {
+        elapsed: 0,
+        action: Action::Stake {
+            user: 0,
+            amount: 1_000_000_000,
+        },
+    })
+    .unwrap();
+    pool.settle_all().unwrap();
+    let paid = pool.users[0].paid;
+    assert!(paid <= pool.funded());
+    assert!(pool.funded() - paid <= 2);
+}
+
+#[test]
+fn rewards_owed_cover_every_pending_claim() {
+    let mut pool = YourPool::try_from_slice(&[0u8; YOUR_POOL_STORAGE_TOTAL_BYTES]).unwrap();
+    pool.acc_type = AccTypesWithVersion::YourPoolDataV2 as u8;
+    pool.your_reward_rate = 7;
+    pool.reward_duration_end = 1_000;
+    let mut users = [User::try_from_slice(&[0u8; USER_STORAGE_TOTAL_BYTES]).unwrap(); 3];
+
+    // nothing is owed for the time nobody is staked
+    for (index, (now, amount)) in [(100, 3u64), (100, 5), (400, 11)].iter().enumerate() {
+        utils::update_rewards(&mut pool, Some(&mut users[index]), *now).unwrap();
+        users[index].balance_your_staked += amount;
+        pool.total_your_staked += amount;
+    }
+    for user in users.iter_mut() {
+        utils::update_rewards(&mut pool, Some(user), 5_000).unwrap();
+    }
+
+    let pending: u64 = users.iter().map(|user| user.reward_per_token_pending).sum();
+    assert_eq!(pool.your_rewards_owed, 7 * 900);
+    assert!(pending <= pool.your_rewards_owed);
+    assert!(pool.your_rewards_owed - pending <= 2 * users.len() as u64);
+}
+
+#[test]
+fn scheduled_epochs_switch_the_rate_at_their_start() {
+    let mut pool = YourPool::try_from_slice(&[0u8; YOUR_POOL_STORAGE_TOTAL_BYTES]).unwrap();
+    pool.acc_type = AccTypesWithVersion::YourPoolDataV2 as u8;
+    pool.your_reward_rate = 7;
+    pool.reward_duration_end = 1_000;
+    pool.total_your_staked = 10;
+    let mut schedule = RewardSchedule {
+        acc_type: AccTypesWithVersion::RewardScheduleV1 as u8,
+        your_pool: Default::default(),
+        next_epoch: 0,
+        epoch_count: 0,
+        epochs: [RewardEpoch::default(); MAX_REWARD_EPOCHS],
+    };
+    for (start_time, duration, amount) in [(1_500, 500, 1_000), (2_000, 100, 1_050)] {
+        schedule
+            .push(RewardEpoch {
+                start_time,
+                duration,
+                amount,
+            })
+            .unwrap();
+        pool.your_rewards_scheduled += amount;
+    }
+    pool.next_epoch_start = 1_500;
+
+    utils::advance_reward_schedule(&mut pool, &mut schedule, 1_499).unwrap();
+    assert_eq!(pool.your_reward_rate, 7);
+
+    // the gap between the first period and the first epoch emits nothing
+    utils::advance_reward_schedule(&mut pool, &mut schedule, 1_600).unwrap();
+    utils::update_rewards(&mut pool, None, 1_600).unwrap();
+    assert_eq!(pool.your_reward_rate, 2);
+    assert_eq!(pool.your_rewards_owed, 7 * 1_000 + 2 * 100);
+    assert_eq!(pool.next_epoch_start, 2_000);
+    assert_eq!(pool.your_rewards_scheduled, 1_050);
+
+    // both boundaries are crossed in one update
+    utils::advance_reward_schedule(&mut pool, &mut schedule, 5_000).unwrap();
+    utils::update_rewards(&mut pool, None, 5_000).unwrap();
+    assert_eq!(pool.your_reward_rate, 10);
+    assert_eq!(pool.reward_duration_end, 2_100);
+    assert_eq!(pool.your_rewards_owed, 7 * 1_000 + 2 * 500 + 10 * 100);
+    assert_eq!(pool.next_epoch_start, 0);
+    assert_eq!(pool.your_rewards_scheduled, 0);
+
+    // applied epochs make room for new ones
+    for start_time in 0..MAX_REWARD_EPOCHS as u64 {
+        schedule
+            .push(RewardEpoch {
+                start_time: 10_000 + start_time,
+                duration: 1,
+                amount: 1,
+            })
+            .unwrap();
+    }
+    assert!(schedule.push(RewardEpoch::default()).is_err());
+}
+
+#[test]
+fn halving_curve_is_integrated_across_intervals() {
+    let curve = utils::EmissionCurve::Halving {
+        initial_rate: 1_000,
+        start: 100,
+        interval: 10,
+    };
+    assert_eq!(curve.rate_at(99), 0);
+    assert_eq!(curve.rate_at(109), 1_000);
+    assert_eq!(curve.rate_at(110), 500);
+    assert_eq!(
+        curve.emitted(105, 125).unwrap(),
+        5 * 1_000 + 10 * 500 + 5 * 250
+    );
+    // 1_000 has ten bits, the rate is zero from the tenth halving on
+    assert_eq!(curve.rate_at(200), 0);
+    assert!(curve.emitted(0, u64::MAX).unwrap() < 2 * 1_000 * 10);
+}
+
+#[test]
+fn halving_pools_owe_what_the_curve_emitted() {
+    let mut pool = YourPool::try_from_slice(&[0u8; YOUR_POOL_STORAGE_TOTAL_BYTES]).unwrap();
+    pool.acc_type = AccTypesWithVersion::YourPoolDataV2 as u8;
+    pool.emission_mode = EmissionMode::Halving as u8;
+    pool.initial_reward_rate = 8;
+    pool.halving_interval = 100;
+    pool.emission_start = 1_000;
+    pool.last_update_time = 1_000;
+    pool.reward_duration_end = 1_400;
+    let mut user = User::try_from_slice(&[0u8; USER_STORAGE_TOTAL_BYTES]).unwrap();
+
+    utils::update_rewards(&mut pool, Some(&mut user), 1_000).unwrap();
+    user.balance_your_staked = 5;
+    pool.total_your_staked = 5;
+
+    utils::update_rewards(&mut pool, Some(&mut user), 1_150).unwrap();
+    assert_eq!(pool.your_reward_rate, 4);
+    assert_eq!(pool.your_rewards_owed, 8 * 100 + 4 * 50);
+
+    utils::update_rewards(&mut pool, Some(&mut user), 9_000).unwrap();
+    assert_eq!(pool.your_reward_rate, 0);
+    assert_eq!(pool.your_rewards_owed, (8 + 4 + 2 + 1) * 100);
+    assert!(pool.your_rewards_owed - user.reward_per_token_pending <= 1);
+}
+
+#[test]
+fn fixed_apr_accrues_on_the_balance_whatever_the_stake() {
+    let year = utils::constants::SECONDS_PER_YEAR;
+    let mut pool = YourPool::try_from_slice(&[0u8; YOUR_POOL_STORAGE_TOTAL_BYTES]).unwrap();
+    pool.acc_type = AccTypesWithVersion::YourPoolDataV2 as u8;
+    pool.emission_mode = EmissionMode::FixedApr as u8;
+    pool.apr_bps = 1_200;
+    pool.reward_duration_end = year;
+    let mut users = [User::try_from_slice(&[0u8; USER_STORAGE_TOTAL_BYTES]).unwrap(); 2];
+
+    utils::update_rewards(&mut pool, Some(&mut users[0]), 0).unwrap();
+    users[0].balance_your_staked = 1_000_000;
+    pool.total_your_staked = 1_000_000;
+    assert_eq!(
+        utils::projected_obligations(&pool, 0).unwrap(),
+        1_000_000 * 12 / 100
+    );
+
+    // a second staker joining halfway does not dilute the first one
+    utils::update_rewards(&mut pool, Some(&mut users[1]), year as i64 / 2).unwrap();
+    users[1].balance_your_staked = 3_000_000;
+    pool.total_your_staked = 4_000_000;
+    assert_eq!(
+        utils::projected_obligations(&pool, year as i64 / 2).unwrap(),
+        60_000 + 4_000_000 * 6 / 100
+    );
+
+    for user in users.iter_mut() {
+        utils::update_rewards(&mut pool, Some(user), 2 * year as i64).unwrap();
+    }
+    assert!(120_000 - users[0].reward_per_token_pending <= 1);
+    assert!(180_000 - users[1].reward_per_token_pending <= 1);
+    assert_eq!(pool.your_rewards_owed, 120_000 + 180_000);
+    assert_eq!(
+        utils::projected_obligations(&pool, 2 * year as i64).unwrap(),
+        pool.your_rewards_owed
+    );
+}
+
+#[test]
+fn claimed_rewards_vest_linearly() {
+    let mut user = User::try_from_slice(&[0u8; USER_STORAGE_TOTAL_BYTES]).unwrap();
+    utils::vest_rewards(&mut user, 1_000, 100, 1_000).unwrap();
+
+    utils::settle_vesting(&mut user, 1_025).unwrap();
+    assert_eq!((user.vesting_locked, user.vesting_unlocked), (750, 250));
+
+    // a new claim does not push back what is already vesting: 500 left for
+    // 50s and 250 for 100s end at the weighted 66s
+    utils::vest_rewards(&mut user, 250, 100, 1_050).unwrap();
+    assert_eq!((user.vesting_locked, user.vesting_unlocked), (750, 500));
+    assert_eq!(user.vesting_end, 1_116);
+
+    utils::settle_vesting(&mut user, 1_100).unwrap();
+    assert_eq!((user.vesting_locked, user.vesting_unlocked), (182, 1_068));
+    utils::settle_vesting(&mut user, 9_000).unwrap();
+    assert_eq!((user.vesting_locked, user.vesting_unlocked), (0, 1_250));
+}
+
+#[test]
+fn redistributed_penalties_stream_to_the_stakers() {
+    let mut pool = YourPool::try_from_slice(&[0u8; YOUR_POOL_STORAGE_TOTAL_BYTES]).unwrap();
+    pool.acc_type = AccTypesWithVersion::YourPoolDataV2 as u8;
+    let mut users = [User::try_from_slice(&[0u8; USER_STORAGE_TOTAL_BYTES]).unwrap(); 3];
+    let day = utils::constants::MIN_DURATION as i64;
+
+    // nobody to hand it to, it stays surplus
+    utils::redistribute_rewards(&mut pool, 100, 0).unwrap();
+    utils::update_rewards(&mut pool, None, day).unwrap();
+    assert_eq!(pool.your_rewards_owed, 0);
+    assert_eq!(pool.penalty_stream_remaining, 0);
+
+    for (user, amount) in users.iter_mut().zip([1u64, 3, 0]) {
+        utils::update_rewards(&mut pool, Some(user), day).unwrap();
+        user.balance_your_staked = amount;
+        pool.total_your_staked += amount;
+    }
+    utils::redistribute_rewards(&mut pool, 400, day).unwrap();
+    assert_eq!(utils::projected_obligations(&pool, day).unwrap(), 400);
+
+    // a stake that joins half way only shares the second half
+    utils::update_rewards(&mut pool, Some(&mut users[2]), day + day / 2).unwrap();
+    assert_eq!(pool.your_rewards_owed, 200);
+    users[2].balance_your_staked = 4;
+    pool.total_your_staked += 4;
+    for user in users.iter_mut() {
+        utils::update_rewards(&mut pool, Some(user), 3 * day).unwrap();
+    }
+    assert_eq!(pool.your_rewards_owed, 400);
+    assert_eq!(pool.penalty_stream_remaining, 0);
+    assert!(75 - users[0].reward_per_token_pending <= 1);
+    assert!(225 - users[1].reward_per_token_pending <= 1);
+    assert!(100 - users[2].reward_per_token_pending <= 1);
+}
+
+#[test]
+fn moved_positions_keep_their_rewards() {
+    let mut pool = YourPool::try_from_slice(&[0u8; YOUR_POOL_STORAGE_TOTAL_BYTES]).unwrap();
+    pool.acc_type = AccTypesWithVersion::YourPoolDataV2 as u8;
+    pool.your_reward_rate = 10;
+    pool.reward_duration_end = 1_000;
+    let mut users = [User::try_from_slice(&[0u8; USER_STORAGE_TOTAL_BYTES]).unwrap(); 3];
+
+    for (user, amount) in users.iter_mut().zip([100u64, 300, 0]) {
+        utils::update_rewards(&mut pool, Some(user), 0).unwrap();
+        user.balance_your_staked = amount;
+        pool.total_your_staked += amount;
+    }
+    pool.stats.active_stakers = 2;
+    users[0].unstake_pending = 50;
+    users[0].claim_timeout_date = 500;
+
+    let (from, to) = users.split_at_mut(2);
+    assert_eq!(
+        utils::move_position(&mut pool, &mut from[0], &mut to[0], 100).unwrap(),
+        100
+    );
+    assert_eq!(from[0].balance_your_staked, 0);
+    assert_eq!(from[0].reward_per_token_pending, 0);
+    assert_eq!(from[0].unstake_pending, 0);
+    assert_eq!(to[0].balance_your_staked, 100);
+    assert!(250 - to[0].reward_per_token_pending <= 1);
+    assert_eq!(to[0].claim_timeout_date, 500);
+    assert_eq!(pool.stats.active_stakers, 2);
+
+    // the moved stake keeps earning its share for the new wallet, each
+    // settlement rounds down once
+    for user in users.iter_mut() {
+        utils::update_rewards(&mut pool, Some(user), 200).unwrap();
+    }
+    assert_eq!(users[0].reward_per_token_pending, 0);
+    assert!(1_500 - users[1].reward_per_token_pending <= 1);
+    assert!(500 - users[2].reward_per_token_pending <= 2);
+    assert_eq!(pool.total_your_staked, 400);
+}
@@ -0,0 +1,274 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+use your_staking::error::CustomError;
+use your_staking::state::{
+    AccTypesWithVersion, PoolStats, User, UserV1, YourPool, YourPoolV1, POOL_STATS_OFFSET,
+    USER_STORAGE_TOTAL_BYTES, USER_V1_STORAGE_TOTAL_BYTES, YOUR_POOL_STORAGE_TOTAL_BYTES,
+    YOUR_POOL_V1_STORAGE_TOTAL_BYTES,
+};
+
+fn pool_v1() -> YourPoolV1 {
+    YourPoolV1 {
+        acc_type: AccTypesWithVersion::YourPoolDataV1 as u8,
+        owner_wallet: Pubkey::new_unique(),
+        your_staking_vault: Pubkey::new_unique(),
+        your_reward_rate: 11,
+        your_epoch_duration: 86400,
+        user_stake_count: 3,
+        pda_nonce: 254,
+        reward_duration_end: 1_700_000_000,
+    }
+}
+
+fn user_v1(pool: Pubkey) -> UserV1 {
+    UserV1 {
+        acc_type: AccTypesWithVersion::UserDataV1 as u8,
+        user_wallet: Pubkey::new_unique(),
+        your_pool: pool,
+        balance_your_staked: 500,
+        unstake_pending: 20,
+        unstake_pending_date: 1_700_000_002,
+        nonce: 253,
+        claim_timeout_date: 1_700_086_400,
+    }
+}
+
+#[test]
+fn layouts_match_storage_sizes() {
+    let pool: YourPool = pool_v1().into();
+    assert_eq!(
+        pool_v1().try_to_vec().unwrap().len(),
+        YOUR_POOL_V1_STORAGE_TOTAL_BYTES
+    );
+    assert_eq!(
+        pool.try_to_vec().unwrap().len(),
+        YOUR_POOL_STORAGE_TOTAL_BYTES
+    );
+
+    let user: User = user_v1(Pubkey::new_unique()).into();
+    assert_eq!(
+        user_v1(Pubkey::new_unique()).try_to_vec().unwrap().len(),
+        USER_V1_STORAGE_TOTAL_BYTES
+    );
+    assert_eq!(user.try_to_vec().unwrap().len(), USER_STORAGE_TOTAL_BYTES);
+}
+
+#[test]
+fn v1_pool_is_widened_and_never_written_back() {
+    let v1 = pool_v1();
+    let mut data = v1.try_to_vec().unwrap();
+
+    let mut pool = YourPool::unpack_from_slice(&data).unwrap();
+    assert_eq!(pool.acc_type, AccTypesWithVersion::YourPoolDataV1 as u8);
+    assert_eq!(pool.owner_wallet, v1.owner_wallet);
+    assert_eq!(pool.user_stake_count, 3);
+    assert_eq!(pool.migrated_from, Pubkey::default());
+    assert_eq!(pool.accepts_stake_migrations, 0);
+
+    pool.user_stake_count = 4;
+    assert_eq!(
+        pool.pack_into_slice(&mut data),
+        Err(CustomError::PoolNotMigrated.into())
+    );
+    let mut wide = vec![0u8; YOUR_POOL_STORAGE_TOTAL_BYTES];
+    assert_eq!(
+        pool.pack_into_slice(&mut wide),
+        Err(CustomError::PoolNotMigrated.into())
+    );
+    assert_eq!(data, v1.try_to_vec().unwrap());
+}
+
+#[test]
+fn v2_pool_round_trips() {
+    let mut pool: YourPool = pool_v1().into();
+    pool.acc_type = AccTypesWithVersion::YourPoolDataV2 as u8;
+    pool.your_rewards_vault = Pubkey::new_unique();
+    pool.accepts_stake_migrations = 1;
+    let mut data = vec![0u8; YOUR_POOL_STORAGE_TOTAL_BYTES];
+    pool.pack_into_slice(&mut data).unwrap();
+
+    let unpacked = YourPool::unpack_from_slice(&data).unwrap();
+    assert_eq!(unpacked.your_rewards_vault, pool.your_rewards_vault);
+    assert_eq!(unpacked.accepts_stake_migrations, 1);
+}
+
+#[test]
+fn size_and_version_must_agree() {
+    let mut pool: YourPool = pool_v1().into();
+    pool.acc_type = AccTypesWithVersion::YourPoolDataV2 as u8;
+    let mut short = vec![0u8; YOUR_POOL_V1_STORAGE_TOTAL_BYTES];
+    assert!(pool.pack_into_slice(&mut short).is_err());
+
+    let mut data = pool_v1().try_to_vec().unwrap();
+    data[0] = AccTypesWithVersion::YourPoolDataV2 as u8;
+    assert!(YourPool::unpack_from_slice(&data).is_err());
+
+    assert!(YourPool::unpack_from_slice(&[]).is_err());
+    assert!(YourPool::unpack_from_slice(&[AccTypesWithVersion::ClosedPool as u8]).is_err());
+    assert!(YourPool::unpack_from_slice(&vec![0u8; YOUR_POOL_STORAGE_TOTAL_BYTES]).is_err());
+}
+
+#[test]
+fn users_unpack_by_version() {
+    let v1 = user_v1(Pubkey::new_unique());
+    let data = v1.try_to_vec().unwrap();
+    let user = User::unpack_from_slice(&data).unwrap();
+    assert_eq!(user.balance_your_staked, 500);
+    let mut v1_data = data.clone();
+    assert_eq!(
+        user.pack_into_slice(&mut v1_data),
+        Err(CustomError::UserNotMigrated.into())
+    );
+
+    let mut migrated = user;
+    migrated.acc_type = AccTypesWithVersion::UserDataV2 as u8;
+    let mut v2_data = vec![0u8; USER_STORAGE_TOTAL_BYTES];
+    migrated.pack_into_slice(&mut v2_data).unwrap();
+    let unpacked = User::unpack_from_slice(&v2_data).unwrap();
+    assert_eq!(unpacked.unstake_pending, 20);
+    assert_eq!(unpacked.claim_timeout_date, v1.claim_timeout_date);
+
+    assert!(User::unpack_from_slice(&v2_data[..USER_V1_STORAGE_TOTAL_BYTES]).is_err());
+}
+
+#[test]
+fn loaders_check_owner_pool_and_wallet() {
+    let program_id = Pubkey::new_unique();
+    let pool_key = Pubkey::new_unique();
+    let mut user: User = user_v1(pool_key).into();
+    user.acc_type = AccTypesWithVersion::UserDataV2 as u8;
+    let user_key = Pubkey::new_unique();
+    let mut data = user.try_to_vec().unwrap();
+    let mut lamports = 0u64;
+    let account = AccountInfo::new(
+        &user_key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+
+    let loaded = User::load_for_wallet(&account, &program_id, &user.user_wallet, &pool_key);
+    assert_eq!(loaded.unwrap().balance_your_staked, 500);
+
+    let other_program = Pubkey::new_unique();
+    assert_eq!(
+        User::load(&account, &other_program, &pool_key).err(),
+        Some(ProgramError::IncorrectProgramId)
+    );
+    assert_eq!(
+        YourPool::load(&account, &other_program).err(),
+        Some(ProgramError::IncorrectProgramId)
+    );
+    assert_eq!(
+        User::load(&account, &program_id, &Pubkey::new_unique()).err(),
+        Some(CustomError::UserPoolMismatched.into())
+    );
+    assert_eq!(
+        User::load_for_wallet(&account, &program_id, &pool_key, &pool_key).err(),
+        Some(CustomError::UserStorageAuthorityMismatched.into())
+    );
+}
+
+#[test]
+fn loaders_send_v1_records_through_migration() {
+    let program_id = Pubkey::new_unique();
+    let pool_key = Pubkey::new_unique();
+    let mut pool_data = pool_v1().try_to_vec().unwrap();
+    let mut pool_lamports = 0u64;
+    let pool_account = AccountInfo::new(
+        &pool_key,
+        false,
+        true,
+        &mut pool_lamports,
+        &mut pool_data,
+        &program_id,
+        false,
+        0,
+    );
+    assert_eq!(
+        YourPool::load(&pool_account, &program_id).err(),
+        Some(CustomError::PoolNotMigrated.into())
+    );
+    assert_eq!(
+        YourPool::load_any_version(&pool_account, &program_id)
+            .unwrap()
+            .user_stake_count,
+        3
+    );
+
+    let user_key = Pubkey::new_unique();
+    let mut user_data = user_v1(pool_key).try_to_vec().unwrap();
+    let mut user_lamports = 0u64;
+    let user_account = AccountInfo::new(
+        &user_key,
+        false,
+        true,
+        &mut user_lamports,
+        &mut user_data,
+        &program_id,
+        false,
+        0,
+    );
+    assert_eq!(
+        User::load(&user_account, &program_id, &pool_key).err(),
+        Some(CustomError::UserNotMigrated.into())
+    );
+    assert_eq!(
+        User::load_any_version(&user_account, &program_id, &pool_key)
+            .unwrap()
+            .balance_your_staked,
+        500
+    );
+}
+
+#[test]
+fn pool_signer_uses_the_stored_bump() {
+    let program_id = Pubkey::new_unique();
+    let pool_key = Pubkey::new_unique();
+    let (pool_signer, bump) = Pubkey::find_program_address(&[&pool_key.to_bytes()], &program_id);
+    let mut pool: YourPool = pool_v1().into();
+    pool.pda_nonce = bump;
+    assert_eq!(
+        pool.pool_signer_address(&pool_key, &program_id),
+        Ok(pool_signer)
+    );
+
+    pool.pda_nonce = bump.wrapping_add(1);
+    assert_ne!(
+        pool.pool_signer_address(&pool_key, &program_id),
+        Ok(pool_signer)
+    );
+}
+
+#[test]
+fn stats_count_active_stakers_at_their_offset() {
+    let mut pool: YourPool = pool_v1().into();
+    pool.acc_type = AccTypesWithVersion::YourPoolDataV2 as u8;
+    pool.stats.record_stake(0, 100);
+    pool.stats.record_stake(100, 50);
+    pool.stats.record_stake(0, 0);
+    assert_eq!(pool.stats.active_stakers, 1);
+    pool.stats.record_unstake(50, 100);
+    assert_eq!(pool.stats.active_stakers, 1);
+    pool.stats.record_unstake(0, 50);
+    assert_eq!(pool.stats.active_stakers, 0);
+    // a migrated user is counted like a first stake
+    pool.stats.record_stake(0, 10);
+    assert_eq!(pool.stats.active_stakers, 1);
+    pool.stats.record_unstake(0, 10);
+    assert_eq!(pool.stats.active_stakers, 0);
+    assert_eq!(pool.stats.total_staked, 160);
+    assert_eq!(pool.stats.total_unstaked, 160);
+    pool.stats.last_activity_date = 1_700_000_000;
+
+    let data = pool.try_to_vec().unwrap();
+    let stats_len = pool.stats.try_to_vec().unwrap().len();
+    assert_eq!(
+        PoolStats::try_from_slice(&data[POOL_STATS_OFFSET..POOL_STATS_OFFSET + stats_len]).unwrap(),
+        pool.stats
+    );
+}
@@ -0,0 +1,164 @@
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, program_option::COption,
+    program_pack::Pack, pubkey::Pubkey,
+};
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+use your_staking::{
+    error::CustomError,
+    processor::enable_receipt_token::{check_receipt_mint, get_receipt_mint_address},
+    state::{AccTypesWithVersion, YourPool, YourPoolV1},
+    token,
+};
+
+fn base_mint() -> Vec<u8> {
+    let mint = Mint {
+        mint_authority: COption::None,
+        supply: 1_000_000,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    let mut data = vec![0u8; Mint::LEN];
+    Mint::pack(mint, &mut data).unwrap();
+    data
+}
+
+/// Token-2022 mint layout: the base mint padded to the account size, the
+/// account type byte and then the TLV extensions.
+fn extended_mint(extensions: &[(u16, Vec<u8>)]) -> Vec<u8> {
+    let mut data = base_mint();
+    data.resize(TokenAccount::LEN, 0);
+    data.push(1);
+    for (extension_type, value) in extensions {
+        data.extend_from_slice(&extension_type.to_le_bytes());
+        data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        data.extend_from_slice(value);
+    }
+    data
+}
+
+fn transfer_fee_config(older: (u64, u64, u16), newer: (u64, u64, u16)) -> (u16, Vec<u8>) {
+    // authorities and withheld amount
+    let mut value = vec![0u8; 72];
+    for (epoch, maximum_fee, basis_points) in [older, newer] {
+        value.extend_from_slice(&epoch.to_le_bytes());
+        value.extend_from_slice(&maximum_fee.to_le_bytes());
+        value.extend_from_slice(&basis_points.to_le_bytes());
+    }
+    (1, value)
+}
+
+#[test]
+fn vault_len_covers_supported_extensions() {
+    let close_authority = (3, vec![0u8; 32]);
+    assert_eq!(token::vault_len(&base_mint()).unwrap(), TokenAccount::LEN);
+    assert_eq!(
+        token::vault_len(&extended_mint(std::slice::from_ref(&close_authority))).unwrap(),
+        TokenAccount::LEN
+    );
+    assert_eq!(
+        token::vault_len(&extended_mint(&[
+            close_authority,
+            transfer_fee_config((0, 0, 0), (0, 0, 0))
+        ]))
+        .unwrap(),
+        TokenAccount::LEN + 1 + 4 + 8
+    );
+
+    // non-transferable
+    assert_eq!(
+        token::vault_len(&extended_mint(&[(9, vec![])])),
+        Err(CustomError::UnsupportedMintExtension.into())
+    );
+}
+
+#[test]
+fn unpacks_extended_token_accounts() {
+    let account = TokenAccount {
+        amount: 42,
+        state: AccountState::Initialized,
+        ..TokenAccount::default()
+    };
+    let mut data = vec![0u8; TokenAccount::LEN];
+    TokenAccount::pack(account, &mut data).unwrap();
+    assert_eq!(token::unpack_account(&data).unwrap().amount, 42);
+
+    data.extend_from_slice(&[2, 0x0f, 0, 8, 0]);
+    data.extend_from_slice(&[0u8; 8]);
+    assert_eq!(token::unpack_account(&data).unwrap().amount, 42);
+
+    // a mint is not a token account
+    data[TokenAccount::LEN] = 1;
+    assert_eq!(
+        token::unpack_account(&data),
+        Err(ProgramError::InvalidAccountData)
+    );
+    assert_eq!(
+        token::unpack_mint(&extended_mint(&[(3, vec![0u8; 32])]))
+            .unwrap()
+            .decimals,
+        6
+    );
+}
+
+#[test]
+fn receipt_mint_must_be_the_pools() {
+    let program_id = Pubkey::new_unique();
+    let pool_key = Pubkey::new_unique();
+    let mut pool: YourPool = YourPoolV1 {
+        acc_type: AccTypesWithVersion::YourPoolDataV1 as u8,
+        owner_wallet: Pubkey::new_unique(),
+        your_staking_vault: Pubkey::new_unique(),
+        your_reward_rate: 0,
+        your_epoch_duration: 86400,
+        user_stake_count: 0,
+        pda_nonce: 254,
+        reward_duration_end: 0,
+    }
+    .into();
+    assert!(!pool.has_receipt_token());
+
+    let receipt_mint = get_receipt_mint_address(&pool_key, &program_id);
+    assert_ne!(
+        receipt_mint,
+        get_receipt_mint_address(&Pubkey::new_unique(), &program_id)
+    );
+    pool.receipt_mint = receipt_mint;
+    assert!(pool.has_receipt_token());
+
+    let token_program = spl_token::id();
+    let mut token_lamports = 0u64;
+    let mut token_data = vec![];
+    let token_account = AccountInfo::new(
+        &token_program,
+        false,
+        false,
+        &mut token_lamports,
+        &mut token_data,
+        &token_program,
+        true,
+        0,
+    );
+    let mut lamports = 0u64;
+    let mut data = base_mint();
+    let mint_account = AccountInfo::new(
+        &receipt_mint,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &token_program,
+        false,
+        0,
+    );
+    assert_eq!(
+        check_receipt_mint(&pool, &mint_account, &token_account),
+        Ok(6)
+    );
+
+    pool.receipt_mint = Pubkey::new_unique();
+    assert_eq!(
+        check_receipt_mint(&pool, &mint_account, &token_account),
+        Err(CustomError::InvalidReceiptMint.into())
+    );
+}